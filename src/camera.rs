@@ -0,0 +1,259 @@
+// free-flying first person camera. yaw turns around the world's up axis, pitch
+// looks up and down and is clamped short of straight up or down, and roll tilts
+// the view around the direction the camera faces.
+use cgmath::{Angle, EuclideanSpace, InnerSpace};
+
+// past this the view would flip over the top
+const MAX_PITCH: f32 = 89.0;
+
+#[derive(Debug, Clone, Copy)]
+pub struct Camera {
+    pub position: cgmath::Point3<f32>,
+    pub yaw: cgmath::Deg<f32>,
+    pub pitch: cgmath::Deg<f32>,
+    pub roll: cgmath::Deg<f32>,
+}
+
+impl Default for Camera {
+    fn default() -> Camera {
+        Camera {
+            position: cgmath::Point3::new(0.0, 0.0, -5.0),
+            yaw: cgmath::Deg(0.0),
+            pitch: cgmath::Deg(0.0),
+            roll: cgmath::Deg(0.0),
+        }
+    }
+}
+
+fn world_up() -> cgmath::Vector3<f32> {
+    cgmath::Vector3::new(0.0, 1.0, 0.0)
+}
+
+impl Camera {
+    // the direction the camera looks in
+    pub fn forward(&self) -> cgmath::Vector3<f32> {
+        let (yaw_sin, yaw_cos) = self.yaw.sin_cos();
+        let (pitch_sin, pitch_cos) = self.pitch.sin_cos();
+        cgmath::Vector3::new(yaw_sin * pitch_cos, pitch_sin, yaw_cos * pitch_cos)
+    }
+
+    // sideways, along the ground. pitch and roll don't change it.
+    pub fn right(&self) -> cgmath::Vector3<f32> {
+        let (yaw_sin, yaw_cos) = (self.yaw - cgmath::Deg(90.0)).sin_cos();
+        cgmath::Vector3::new(yaw_sin, 0.0, yaw_cos)
+    }
+
+    // the top of the view, tilted by roll
+    pub fn up(&self) -> cgmath::Vector3<f32> {
+        let forward = self.forward();
+        let right = forward.cross(world_up()).normalize();
+        let up = right.cross(forward);
+        let (roll_sin, roll_cos) = self.roll.sin_cos();
+        up * roll_cos + right * roll_sin
+    }
+
+    pub fn view(&self) -> cgmath::Matrix4<f32> {
+        cgmath::Matrix4::look_at_dir(self.position, self.forward(), self.up())
+    }
+
+    // turn by the given amounts, keeping pitch in range
+    pub fn rotate(&mut self, yaw: cgmath::Deg<f32>, pitch: cgmath::Deg<f32>) {
+        self.yaw = (self.yaw + yaw).normalize();
+        self.pitch = cgmath::Deg((self.pitch + pitch).0.clamp(-MAX_PITCH, MAX_PITCH));
+    }
+
+    // `forward`, `right` and `up` units along the camera's forward and right
+    // directions and the world's up axis
+    pub fn direction(&self, forward: f32, right: f32, up: f32) -> cgmath::Vector3<f32> {
+        self.forward() * forward + self.right() * right + world_up() * up
+    }
+}
+
+// seconds per step of the camera motion. stepping by a fixed amount, however long
+// frames take, makes the motion the same at any frame rate.
+const MOTION_STEP: f32 = 1.0 / 120.0;
+
+// frames longer than this, e.g. after a breakpoint, are treated as this long
+const MAX_MOTION_FRAME: f32 = 0.25;
+
+#[derive(Debug, Clone, Copy)]
+pub struct MotionSettings {
+    // units per second
+    pub max_speed: f32,
+    // how quickly the velocity turns towards the held direction at max speed,
+    // in units per second per second
+    pub acceleration: f32,
+    // how quickly the camera slows once nothing is held. each second the speed is
+    // multiplied by e^-damping.
+    pub damping: f32,
+}
+
+// velocity of the fly camera, which speeds up while keys are held and coasts to a
+// stop when they're let go
+#[derive(Debug)]
+pub struct Motion {
+    velocity: cgmath::Vector3<f32>,
+    // time not yet stepped
+    remainder: f32,
+}
+
+impl Default for Motion {
+    fn default() -> Motion {
+        Motion {
+            velocity: cgmath::Vector3::new(0.0, 0.0, 0.0),
+            remainder: 0.0,
+        }
+    }
+}
+
+impl Motion {
+    pub fn stop(&mut self) {
+        self.velocity = cgmath::Vector3::new(0.0, 0.0, 0.0);
+    }
+
+    // move `camera` for `seconds`, accelerating along `direction` (which needn't be
+    // normalized, and is zero when nothing is held)
+    pub fn update(&mut self, camera: &mut Camera, direction: cgmath::Vector3<f32>, settings: &MotionSettings, seconds: f32) {
+        let direction = if direction.magnitude2() > 0.0 { direction.normalize() } else { direction };
+
+        self.remainder += seconds.min(MAX_MOTION_FRAME);
+        while self.remainder >= MOTION_STEP {
+            self.remainder -= MOTION_STEP;
+
+            if direction.magnitude2() > 0.0 {
+                let change = direction * settings.max_speed - self.velocity;
+                let max_change = settings.acceleration * MOTION_STEP;
+                self.velocity += if change.magnitude() > max_change { change.normalize_to(max_change) } else { change };
+            } else {
+                self.velocity *= (-settings.damping * MOTION_STEP).exp();
+            }
+
+            camera.position += self.velocity * MOTION_STEP;
+        }
+    }
+}
+
+// closest the orbit camera can get to its target
+const MIN_ORBIT_DISTANCE: f32 = 0.1;
+
+// a camera that circles a target point, for looking at a model from all sides.
+// it's kept alongside the fly camera and converted to and from it, so switching
+// between the two doesn't move the view.
+#[derive(Debug, Clone, Copy)]
+pub struct OrbitCamera {
+    pub target: cgmath::Point3<f32>,
+    pub distance: f32,
+    pub yaw: cgmath::Deg<f32>,
+    pub pitch: cgmath::Deg<f32>,
+}
+
+impl OrbitCamera {
+    // orbit whatever is `distance` in front of `camera`
+    pub fn from_camera(camera: &Camera, distance: f32) -> OrbitCamera {
+        let distance = distance.max(MIN_ORBIT_DISTANCE);
+        OrbitCamera {
+            target: camera.position + camera.forward() * distance,
+            distance,
+            yaw: camera.yaw,
+            pitch: camera.pitch,
+        }
+    }
+
+    // the fly camera at the same place, looking the same way
+    pub fn camera(&self, roll: cgmath::Deg<f32>) -> Camera {
+        let mut camera = Camera { position: self.target, yaw: self.yaw, pitch: self.pitch, roll };
+        camera.position = self.target - camera.forward() * self.distance;
+        camera
+    }
+
+    pub fn rotate(&mut self, yaw: cgmath::Deg<f32>, pitch: cgmath::Deg<f32>) {
+        self.yaw = (self.yaw + yaw).normalize();
+        self.pitch = cgmath::Deg((self.pitch + pitch).0.clamp(-MAX_PITCH, MAX_PITCH));
+    }
+
+    // move towards (factor < 1) or away from (factor > 1) the target
+    pub fn zoom(&mut self, factor: f32) {
+        self.distance = (self.distance * factor).max(MIN_ORBIT_DISTANCE);
+    }
+
+    // slide the target sideways and up, in the plane of the view
+    pub fn pan(&mut self, right: f32, up: f32) {
+        let camera = self.camera(cgmath::Deg(0.0));
+        self.target += camera.right() * right + camera.up() * up;
+    }
+
+    // aim at the middle of the box from `min` to `max`, far enough back that
+    // all of it fits within a vertical field of view of `fov`
+    pub fn frame(&mut self, min: cgmath::Point3<f32>, max: cgmath::Point3<f32>, fov: cgmath::Deg<f32>) {
+        self.target = cgmath::Point3::from_vec((min.to_vec() + max.to_vec()) * 0.5);
+        let radius = (max - min).magnitude() * 0.5;
+        self.distance = (radius / (fov * 0.5).sin()).max(MIN_ORBIT_DISTANCE);
+    }
+}
+
+// mouse drags and scrolling for the orbit camera, collected from window events
+// and taken once a frame. the left button rotates and the middle button pans.
+#[derive(Debug, Default)]
+pub struct OrbitInput {
+    cursor: Option<[f64;2]>,
+    rotating: bool,
+    panning: bool,
+    // pixels dragged, and lines scrolled, since the last take
+    rotate: [f64;2],
+    pan: [f64;2],
+    scroll: f32,
+}
+
+impl OrbitInput {
+    pub fn handle_event(&mut self, event: &glutin::WindowEvent) {
+        use glutin::WindowEvent::*;
+        match event {
+            CursorMoved{position, ..} => {
+                let position = [position.x, position.y];
+                if let Some(last) = self.cursor {
+                    let delta = [position[0] - last[0], position[1] - last[1]];
+                    if self.rotating {
+                        self.rotate = [self.rotate[0] + delta[0], self.rotate[1] + delta[1]];
+                    }
+                    if self.panning {
+                        self.pan = [self.pan[0] + delta[0], self.pan[1] + delta[1]];
+                    }
+                }
+                self.cursor = Some(position);
+            },
+            CursorLeft{..} => self.cursor = None,
+            MouseInput{state, button, ..} => {
+                let pressed = *state == glutin::ElementState::Pressed;
+                match button {
+                    glutin::MouseButton::Left => self.rotating = pressed,
+                    glutin::MouseButton::Middle => self.panning = pressed,
+                    _ => (),
+                }
+            },
+            MouseWheel{delta, ..} => {
+                self.scroll += match delta {
+                    glutin::MouseScrollDelta::LineDelta(_, y) => *y,
+                    // roughly one line per 20 pixels, as a touchpad scrolls
+                    glutin::MouseScrollDelta::PixelDelta(position) => position.y as f32 / 20.0,
+                };
+            },
+            _ => (),
+        }
+    }
+
+    // (pixels dragged with the left button, pixels dragged with the middle button,
+    // lines scrolled) since the last call
+    pub fn take(&mut self) -> ([f64;2], [f64;2], f32) {
+        let taken = (self.rotate, self.pan, self.scroll);
+        self.rotate = [0.0, 0.0];
+        self.pan = [0.0, 0.0];
+        self.scroll = 0.0;
+        taken
+    }
+
+    // let go of any drag, e.g. when the window loses focus
+    pub fn release(&mut self) {
+        self.rotating = false;
+        self.panning = false;
+    }
+}
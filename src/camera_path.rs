@@ -0,0 +1,244 @@
+// camera poses over time. a path is either recorded frame by frame while flying
+// around, or written by hand as a few keyframes. playing one back goes through the
+// positions on a catmull-rom spline and slerps between the orientations.
+//
+// path files have one pose per line: `time x y z yaw pitch roll`, with time in
+// seconds from the start of the path and angles in degrees. lines starting with #
+// are comments.
+use crate::camera::Camera;
+use cgmath::{EuclideanSpace, InnerSpace, Matrix3, Quaternion};
+
+#[derive(Debug, Clone, Copy)]
+pub struct Pose {
+    pub time: f32,
+    pub camera: Camera,
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct CameraPath {
+    // in order of time
+    poses: Vec<Pose>,
+}
+
+impl CameraPath {
+    pub fn new() -> CameraPath {
+        CameraPath::default()
+    }
+
+    // add a pose, which has to come after the last one
+    pub fn push(&mut self, time: f32, camera: Camera) {
+        if self.poses.last().is_none_or(|last| time > last.time) {
+            self.poses.push(Pose { time, camera });
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.poses.len()
+    }
+
+    pub fn duration(&self) -> f32 {
+        self.poses.last().map_or(0.0, |last| last.time)
+    }
+
+    pub fn load<P: AsRef<std::path::Path>>(path: P) -> Result<CameraPath, Box<dyn std::error::Error>> {
+        let text = std::fs::read_to_string(path)?;
+        Ok(CameraPath::parse(&text)?)
+    }
+
+    // the path in a path file's text
+    fn parse(text: &str) -> Result<CameraPath, crate::Error> {
+        let mut camera_path = CameraPath::new();
+        for (number, line) in text.lines().enumerate() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+
+            let values = line.split_whitespace().map(|v| v.parse::<f32>()).collect::<Result<Vec<_>, _>>()
+                .map_err(|e| crate::Error::new(format!("line {}: {}", number + 1, e)))?;
+            let (time, camera) = match values[..] {
+                [time, x, y, z, yaw, pitch, roll] => (time, Camera {
+                    position: cgmath::Point3::new(x, y, z),
+                    yaw: cgmath::Deg(yaw),
+                    pitch: cgmath::Deg(pitch),
+                    roll: cgmath::Deg(roll),
+                }),
+                _ => return Err(crate::Error::new(format!("line {}: expected 7 numbers", number + 1))),
+            };
+            if camera_path.poses.last().is_some_and(|last| time <= last.time) {
+                return Err(crate::Error::new(format!("line {}: times have to increase", number + 1)));
+            }
+            camera_path.push(time, camera);
+        }
+
+        if camera_path.poses.is_empty() {
+            return Err(crate::Error::new("the path has no poses"));
+        }
+        Ok(camera_path)
+    }
+
+    pub fn save<P: AsRef<std::path::Path>>(&self, path: P) -> std::io::Result<()> {
+        let mut text = String::from("# time x y z yaw pitch roll\n");
+        for pose in self.poses.iter() {
+            let c = &pose.camera;
+            text.push_str(&format!("{} {} {} {} {} {} {}\n",
+                pose.time, c.position.x, c.position.y, c.position.z, c.yaw.0, c.pitch.0, c.roll.0));
+        }
+        std::fs::write(path, text)
+    }
+
+    // the camera at `time`, which is clamped to the length of the path
+    pub fn sample(&self, time: f32) -> Option<Camera> {
+        let last = self.poses.len().checked_sub(1)?;
+        let next = self.poses.iter().position(|p| p.time > time).unwrap_or(last + 1);
+        if next == 0 {
+            return Some(self.poses[0].camera);
+        }
+        if next > last {
+            return Some(self.poses[last].camera);
+        }
+
+        let (a, b) = (&self.poses[next - 1], &self.poses[next]);
+        let t = (time - a.time) / (b.time - a.time);
+
+        // the poses either side of the segment, repeating the ends
+        let before = &self.poses[next.saturating_sub(2)];
+        let after = &self.poses[(next + 1).min(last)];
+        let position = catmull_rom(
+            before.camera.position.to_vec(),
+            a.camera.position.to_vec(),
+            b.camera.position.to_vec(),
+            after.camera.position.to_vec(),
+            t);
+
+        let from = orientation(&a.camera);
+        let mut to = orientation(&b.camera);
+        // go the short way round
+        if from.dot(to) < 0.0 {
+            to = -to;
+        }
+        let mut camera = camera_from_orientation(from.slerp(to, t));
+        camera.position = cgmath::Point3::from_vec(position);
+        Some(camera)
+    }
+}
+
+fn catmull_rom(p0: cgmath::Vector3<f32>, p1: cgmath::Vector3<f32>, p2: cgmath::Vector3<f32>, p3: cgmath::Vector3<f32>, t: f32)
+    -> cgmath::Vector3<f32>
+{
+    let t2 = t * t;
+    let t3 = t2 * t;
+    (p1 * 2.0
+        + (p2 - p0) * t
+        + (p0 * 2.0 - p1 * 5.0 + p2 * 4.0 - p3) * t2
+        + (p1 * 3.0 - p0 - p2 * 3.0 + p3) * t3) * 0.5
+}
+
+// the rotation taking +z to the camera's forward direction and +y to its up
+fn orientation(camera: &Camera) -> Quaternion<f32> {
+    let forward = camera.forward();
+    let up = camera.up();
+    Quaternion::from(Matrix3::from_cols(up.cross(forward), up, forward))
+}
+
+fn camera_from_orientation(orientation: Quaternion<f32>) -> Camera {
+    let forward = orientation * cgmath::Vector3::unit_z();
+    let up = orientation * cgmath::Vector3::unit_y();
+
+    let mut camera = Camera {
+        position: cgmath::Point3::new(0.0, 0.0, 0.0),
+        yaw: cgmath::Rad(forward.x.atan2(forward.z)).into(),
+        pitch: cgmath::Rad(forward.y.clamp(-1.0, 1.0).asin()).into(),
+        roll: cgmath::Deg(0.0),
+    };
+    // how far `up` is turned from the up of the same camera without roll
+    let level_up = camera.up();
+    let level_right = camera.forward().cross(level_up);
+    camera.roll = cgmath::Rad(up.dot(level_right).atan2(up.dot(level_up))).into();
+    camera
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn camera(x: f32, yaw: f32, pitch: f32, roll: f32) -> Camera {
+        Camera {
+            position: cgmath::Point3::new(x, 0.0, 0.0),
+            yaw: cgmath::Deg(yaw),
+            pitch: cgmath::Deg(pitch),
+            roll: cgmath::Deg(roll),
+        }
+    }
+
+    // the difference between two angles, the short way round
+    fn angle_between(a: cgmath::Deg<f32>, b: cgmath::Deg<f32>) -> f32 {
+        let difference = (a.0 - b.0).rem_euclid(360.0);
+        difference.min(360.0 - difference)
+    }
+
+    fn assert_same_camera(a: &Camera, b: &Camera) {
+        assert!((a.position - b.position).magnitude() < 0.0001, "{:?} != {:?}", a, b);
+        assert!(angle_between(a.yaw, b.yaw) < 0.01, "{:?} != {:?}", a, b);
+        assert!(angle_between(a.pitch, b.pitch) < 0.01, "{:?} != {:?}", a, b);
+        assert!(angle_between(a.roll, b.roll) < 0.01, "{:?} != {:?}", a, b);
+    }
+
+    fn error(text: &str) -> String {
+        CameraPath::parse(text).unwrap_err().to_string()
+    }
+
+    #[test]
+    fn parses_poses_and_skips_comments() {
+        let path = CameraPath::parse("# time x y z yaw pitch roll\n\n0 1 2 3 10 20 30\n  1.5 4 5 6 40 50 60  \n").unwrap();
+        assert_eq!(path.len(), 2);
+        assert_eq!(path.duration(), 1.5);
+    }
+
+    #[test]
+    fn rejects_bad_lines() {
+        assert_eq!(error("0 1 2 3 4 5 6\n1 2 3 4 5 6\n"), "line 2: expected 7 numbers");
+        assert!(error("0 1 2 three 4 5 6\n").starts_with("line 1: "));
+    }
+
+    #[test]
+    fn rejects_times_that_dont_increase() {
+        assert_eq!(error("1 0 0 0 0 0 0\n0.5 0 0 0 0 0 0\n"), "line 2: times have to increase");
+        assert_eq!(error("1 0 0 0 0 0 0\n1 0 0 0 0 0 0\n"), "line 2: times have to increase");
+    }
+
+    #[test]
+    fn rejects_empty_paths() {
+        assert_eq!(error(""), "the path has no poses");
+        assert_eq!(error("# just a comment\n"), "the path has no poses");
+    }
+
+    #[test]
+    fn samples_clamp_to_the_ends() {
+        let mut path = CameraPath::new();
+        assert!(path.sample(0.0).is_none());
+
+        let (first, last) = (camera(0.0, 0.0, 0.0, 0.0), camera(10.0, 90.0, 20.0, 45.0));
+        path.push(1.0, first);
+        path.push(3.0, last);
+        assert_same_camera(&path.sample(-5.0).unwrap(), &first);
+        assert_same_camera(&path.sample(1.0).unwrap(), &first);
+        assert_same_camera(&path.sample(3.0).unwrap(), &last);
+        assert_same_camera(&path.sample(100.0).unwrap(), &last);
+
+        let middle = path.sample(2.0).unwrap();
+        assert!((middle.position.x - 5.0).abs() < 0.0001);
+    }
+
+    #[test]
+    fn orientation_round_trips() {
+        for &yaw in [0.0, 45.0, 170.0, -100.0].iter() {
+            for &pitch in [0.0, 30.0, -60.0, 85.0].iter() {
+                for &roll in [0.0, 30.0, -90.0, 150.0].iter() {
+                    let original = camera(0.0, yaw, pitch, roll);
+                    assert_same_camera(&camera_from_orientation(orientation(&original)), &original);
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,152 @@
+use crate::text_renderer::TextRenderer;
+use std::collections::{BTreeMap, VecDeque};
+
+const SCROLLBACK_LINES: usize = 500;
+const PROMPT: &str = "> ";
+
+// lines logged from anywhere (including the gl debug callback, which may run on
+// another thread) wait here until a console picks them up
+static PENDING_LOG: std::sync::Mutex<Vec<String>> = std::sync::Mutex::new(Vec::new());
+
+pub fn log<S: Into<String>>(line: S) {
+    if let Ok(mut pending) = PENDING_LOG.lock() {
+        pending.push(line.into());
+    }
+}
+
+// a command gets the arguments after its name, and returns text to print on success
+pub type CommandHandler<T> = fn(&mut T, &[&str]) -> Result<String, String>;
+
+struct Command<T> {
+    usage: String,
+    handler: CommandHandler<T>,
+}
+
+// in-window console. commands are registered by name and run against a `T`
+// that the caller passes in, so they can change whatever state `T` exposes.
+pub struct Console<T> {
+    visible: bool,
+    input: String,
+    scrollback: VecDeque<String>,
+    commands: BTreeMap<String, Command<T>>,
+}
+
+impl<T> Console<T> {
+    pub fn new() -> Console<T> {
+        Console {
+            visible: false,
+            input: String::new(),
+            scrollback: VecDeque::new(),
+            commands: BTreeMap::new(),
+        }
+    }
+
+    pub fn register(&mut self, name: &str, usage: &str, handler: CommandHandler<T>) {
+        self.commands.insert(name.to_string(), Command { usage: usage.to_string(), handler });
+    }
+
+    pub fn is_visible(&self) -> bool {
+        self.visible
+    }
+
+    pub fn toggle(&mut self) {
+        self.visible = !self.visible;
+        self.input.clear();
+    }
+
+    pub fn print<S: Into<String>>(&mut self, text: S) {
+        for line in text.into().lines() {
+            if self.scrollback.len() == SCROLLBACK_LINES {
+                self.scrollback.pop_front();
+            }
+            self.scrollback.push_back(line.to_string());
+        }
+    }
+
+    // move anything sent to `log` into the scrollback
+    pub fn drain_log(&mut self) {
+        let pending = match PENDING_LOG.lock() {
+            Ok(mut pending) => std::mem::take(&mut *pending),
+            Err(_) => return,
+        };
+        for line in pending {
+            self.print(line);
+        }
+    }
+
+    // feed a glutin ReceivedCharacter into the input line, running it on enter
+    pub fn receive_char(&mut self, c: char, target: &mut T) {
+        if !self.visible {
+            return;
+        }
+
+        match c {
+            '\r' | '\n' => {
+                let line = std::mem::take(&mut self.input);
+                self.print(format!("{}{}", PROMPT, line));
+                self.execute(&line, target);
+            },
+            '\u{8}' | '\u{7f}' => {
+                self.input.pop();
+            },
+            // the toggle key
+            '`' => (),
+            c if !c.is_control() => self.input.push(c),
+            _ => (),
+        }
+    }
+
+    pub fn execute(&mut self, line: &str, target: &mut T) {
+        let words: Vec<&str> = line.split_whitespace().collect();
+        let (name, args) = match words.split_first() {
+            Some((name, args)) => (*name, args),
+            None => return,
+        };
+
+        if name == "help" {
+            let usages: Vec<String> = self.commands.values().map(|c| c.usage.clone()).collect();
+            self.print("help");
+            for usage in usages {
+                self.print(usage);
+            }
+            return;
+        }
+
+        let handler = match self.commands.get(name) {
+            Some(command) => command.handler,
+            None => {
+                self.print(format!("unknown command `{}`, try `help`", name));
+                return;
+            }
+        };
+
+        match handler(target, args) {
+            Ok(output) => self.print(output),
+            Err(e) => self.print(format!("{}: {}", name, e)),
+        }
+    }
+
+    // scrollback fills the top half of the window, with the input line under it
+    pub fn draw(&self, renderer: &dyn TextRenderer, window_size: (u32, u32)) {
+        if !self.visible || window_size.0 == 0 || window_size.1 == 0 {
+            return;
+        }
+
+        let pixels = 14.0;
+        let line_height = pixels * 1.6;
+        let xscale = 2.0 * pixels / window_size.0 as f32;
+        let yscale = 2.0 * pixels / window_size.1 as f32;
+        let line_step = 2.0 * line_height / window_size.1 as f32;
+        let x = -1.0 + 2.0 * 8.0 / window_size.0 as f32;
+
+        let mut y = 0.0;
+        renderer.draw_text(&format!("{}{}_", PROMPT, self.input), x, y, xscale, yscale);
+        for line in self.scrollback.iter().rev() {
+            y += line_step;
+            if y > 1.0 - line_step {
+                break;
+            }
+            renderer.draw_text(line, x, y, xscale, yscale);
+        }
+    }
+}
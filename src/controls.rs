@@ -0,0 +1,238 @@
+// the camera and everything that moves it: the fly camera and its motion, the
+// orbit camera and the mouse input for it. none of it needs the window, so real
+// events, replayed ones and tests all go through the same handling.
+use crate::camera::{Camera, Motion, OrbitCamera, OrbitInput};
+use crate::cvars::Cvars;
+use crate::input_map::InputMap;
+use crate::projection::Projection;
+use cgmath::{EuclideanSpace, InnerSpace};
+
+// the scene's (min, max) box
+pub type Bounds = (cgmath::Point3<f32>, cgmath::Point3<f32>);
+
+// distance to the orbit target is multiplied by this for each line scrolled
+const ORBIT_ZOOM_PER_LINE: f32 = 0.9;
+
+const DEFAULT_ORBIT_DISTANCE: f32 = 5.0;
+
+#[derive(Debug, Default)]
+pub struct Controls {
+    pub camera: Camera,
+    motion: Motion,
+    // Some while in orbit mode
+    pub orbit: Option<OrbitCamera>,
+    orbit_input: OrbitInput,
+    // mouse look since the last update
+    mouse_delta: (f64, f64),
+    frame_requested: bool,
+}
+
+impl Controls {
+    pub fn new() -> Controls {
+        Controls::default()
+    }
+
+    // put the camera somewhere, stopping it and leaving orbit mode
+    pub fn place(&mut self, camera: Camera) {
+        self.camera = camera;
+        self.motion.stop();
+        self.orbit = None;
+    }
+
+    // pass a window event on to the input map, and to the orbit camera's mouse
+    // input. while the console is open they don't see events, so typing doesn't
+    // move anything.
+    pub fn handle_event(&mut self, event: &glutin::WindowEvent, input_map: &mut InputMap, console_open: bool) {
+        if !console_open {
+            if self.orbit.is_some() {
+                self.orbit_input.handle_event(event);
+            }
+            input_map.handle_event(event);
+        }
+        // the releases of anything held won't arrive
+        if let glutin::WindowEvent::Focused(false) = event {
+            self.orbit_input.release();
+            input_map.release_all();
+        }
+    }
+
+    // gamepads, like keys, don't move anything while the console is open
+    pub fn handle_gamepad_event(&mut self, event: &gilrs::EventType, input_map: &mut InputMap, console_open: bool) {
+        if !console_open {
+            input_map.handle_gamepad_event(event);
+        }
+    }
+
+    // raw mouse motion, while the mouse is grabbed for mouse look
+    pub fn add_mouse_motion(&mut self, delta: (f64, f64)) {
+        self.mouse_delta.0 += delta.0;
+        self.mouse_delta.1 += delta.1;
+    }
+
+    // the camera's actions that happen once per press
+    pub fn handle_actions(&mut self, input: &InputMap, bounds: Option<Bounds>) {
+        if input.just_pressed("toggle_orbit") {
+            self.orbit = match self.orbit {
+                Some(_) => None,
+                None => Some(orbit_around(&self.camera, bounds)),
+            };
+            self.orbit_input = OrbitInput::default();
+        }
+        if input.just_pressed("frame_scene") {
+            self.frame_requested = true;
+        }
+    }
+
+    // move the camera on by `seconds`. returns the height an orthographic view was
+    // zoomed or framed to, if it was, since that's the ortho_height cvar's to keep.
+    pub fn update(&mut self, input: &InputMap, cvars: &Cvars, projection: &Projection, window_height: u32,
+        bounds: Option<Bounds>, seconds: f32) -> Option<f32>
+    {
+        let mut ortho_height = None;
+        let turn = cgmath::Deg(cvars.float("camera_turn_speed") * seconds);
+
+        // moving the mouse right or down turns right or looks down
+        let sensitivity = cvars.float("mouse_sensitivity");
+        let invert = if cvars.bool("invert_mouse_y") { -1.0 } else { 1.0 };
+        let yaw = turn * input.axis("turn_left", "turn_right") + cgmath::Deg(-self.mouse_delta.0 as f32 * sensitivity);
+        let pitch = turn * input.axis("look_up", "look_down") + cgmath::Deg(-self.mouse_delta.1 as f32 * sensitivity * invert);
+        self.mouse_delta = (0.0, 0.0);
+        self.camera.roll += turn * input.axis("roll_right", "roll_left");
+
+        if self.frame_requested {
+            let mut framed = self.orbit.unwrap_or_else(|| orbit_around(&self.camera, bounds));
+            if let Some((min, max)) = bounds {
+                match projection.fov() {
+                    Some(fov) => framed.frame(min, max, fov),
+                    None => {
+                        // far enough back to be in front of everything, though
+                        // the distance doesn't change how big it looks
+                        framed.frame(min, max, cgmath::Deg(90.0));
+                        ortho_height = Some((max - min).magnitude());
+                    },
+                }
+            }
+            self.orbit = Some(framed);
+            self.frame_requested = false;
+        }
+
+        match self.orbit.as_mut() {
+            Some(orbit) => {
+                // dragging moves the scene with the cursor
+                let (drag, pan, scroll) = self.orbit_input.take();
+                let drag_sensitivity = cvars.float("orbit_sensitivity");
+                orbit.rotate(
+                    yaw + cgmath::Deg(drag[0] as f32 * drag_sensitivity),
+                    pitch - cgmath::Deg(drag[1] as f32 * drag_sensitivity));
+
+                // world units per pixel, at the target
+                let scale = projection.units_per_pixel(orbit.distance, window_height);
+                orbit.pan(-pan[0] as f32 * scale, pan[1] as f32 * scale);
+
+                let zoom = ORBIT_ZOOM_PER_LINE.powf(scroll) * (-input.axis("move_forward", "move_back") * seconds).exp();
+                match projection {
+                    Projection::Orthographic { .. } if zoom != 1.0 => {
+                        ortho_height = Some(ortho_height.unwrap_or_else(|| cvars.float("ortho_height")) * zoom);
+                    },
+                    _ => orbit.zoom(zoom),
+                }
+                self.camera = orbit.camera(self.camera.roll);
+                self.motion.stop();
+            },
+            None => {
+                self.camera.rotate(yaw, pitch);
+
+                let mut max_speed = cvars.float("camera_speed");
+                if input.pressed("sprint") {
+                    max_speed *= cvars.float("camera_sprint_multiplier");
+                }
+                if input.pressed("slow") {
+                    max_speed *= cvars.float("camera_slow_multiplier");
+                }
+                let settings = crate::camera::MotionSettings {
+                    max_speed,
+                    acceleration: cvars.float("camera_acceleration"),
+                    damping: cvars.float("camera_damping"),
+                };
+                let direction = self.camera.direction(
+                    input.axis("move_forward", "move_back"),
+                    input.axis("strafe_right", "strafe_left"),
+                    input.axis("move_up", "move_down"));
+                self.motion.update(&mut self.camera, direction, &settings, seconds);
+            },
+        }
+        ortho_height
+    }
+}
+
+// an orbit camera with the same view as `camera`, circling the middle of the scene
+// if that's in front of it
+fn orbit_around(camera: &Camera, bounds: Option<Bounds>) -> OrbitCamera {
+    let distance = bounds
+        .map(|(min, max)| (min.to_vec() + max.to_vec()) * 0.5 - camera.position.to_vec())
+        .map(|to_center| to_center.dot(camera.forward()))
+        .filter(|distance| *distance > 0.0)
+        .unwrap_or(DEFAULT_ORBIT_DISTANCE);
+    OrbitCamera::from_camera(camera, distance)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // steps through a recorded log the way a replay does
+    fn replay(path: &str) -> Controls {
+        let text = std::fs::read_to_string(path).unwrap();
+        let frames = crate::event_log::parse_log(&text).unwrap();
+        let cvars = crate::default_cvars();
+        let projection = Projection::from_cvars(&cvars, false);
+        let mut input_map = InputMap::new();
+        let mut controls = Controls::new();
+        for frame in frames.iter() {
+            input_map.new_frame();
+            for event in frame.events.iter() {
+                controls.handle_event(&event.to_window_event(), &mut input_map, false);
+            }
+            controls.handle_actions(&input_map, None);
+            controls.update(&input_map, &cvars, &projection, 600, None, frame.seconds as f32);
+        }
+        controls
+    }
+
+    #[test]
+    fn turn_and_fly_replays() {
+        let controls = replay(concat!(env!("CARGO_MANIFEST_DIR"), "/data/turn_and_fly.events"));
+        let c = &controls.camera;
+        let pose = [c.position.x, c.position.y, c.position.z, c.yaw.0, c.pitch.0, c.roll.0];
+        let expected = [2.9002, 0.0, -3.3256, 60.0, 0.0, -30.0];
+        for (value, expected) in pose.iter().zip(expected.iter()) {
+            assert!((value - expected).abs() < 0.01, "ended at {:?}, expected {:?}", pose, expected);
+        }
+        assert!(controls.orbit.is_none());
+    }
+
+    #[test]
+    fn losing_focus_lets_go_of_keys() {
+        let cvars = crate::default_cvars();
+        let projection = Projection::from_cvars(&cvars, false);
+        let mut input_map = InputMap::new();
+        let mut controls = Controls::new();
+        let press = crate::event_log::Recorded::Key { scancode: 30, key: Some(glutin::VirtualKeyCode::A), pressed: true };
+        controls.handle_event(&press.to_window_event(), &mut input_map, false);
+        controls.handle_event(&glutin::WindowEvent::Focused(false), &mut input_map, false);
+        controls.update(&input_map, &cvars, &projection, 600, None, 1.0);
+        assert_eq!(controls.camera.yaw, cgmath::Deg(0.0));
+    }
+
+    #[test]
+    fn the_console_keeps_keys_from_the_camera() {
+        let cvars = crate::default_cvars();
+        let projection = Projection::from_cvars(&cvars, false);
+        let mut input_map = InputMap::new();
+        let mut controls = Controls::new();
+        let press = crate::event_log::Recorded::Key { scancode: 30, key: Some(glutin::VirtualKeyCode::A), pressed: true };
+        controls.handle_event(&press.to_window_event(), &mut input_map, true);
+        controls.update(&input_map, &cvars, &projection, 600, None, 0.5);
+        assert_eq!(controls.camera.yaw, cgmath::Deg(0.0));
+    }
+}
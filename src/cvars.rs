@@ -0,0 +1,230 @@
+// named, typed settings that can be changed while running, e.g. from the console,
+// and are saved to a config file between runs
+use std::collections::{BTreeMap, HashMap};
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum CvarValue {
+    Bool(bool),
+    Float(f32),
+    Str(String),
+}
+
+impl CvarValue {
+    // parse `text` as a value of the same type as self
+    fn parse_same_type(&self, text: &str) -> Result<CvarValue, String> {
+        match self {
+            CvarValue::Bool(_) => text.parse().map(CvarValue::Bool).map_err(|e| e.to_string()),
+            CvarValue::Float(_) => text.parse().map(CvarValue::Float).map_err(|e| e.to_string()),
+            CvarValue::Str(_) => Ok(CvarValue::Str(text.to_string())),
+        }
+    }
+
+    fn type_name(&self) -> &'static str {
+        match self {
+            CvarValue::Bool(_) => "bool",
+            CvarValue::Float(_) => "float",
+            CvarValue::Str(_) => "string",
+        }
+    }
+}
+
+impl std::fmt::Display for CvarValue {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self {
+            CvarValue::Bool(v) => write!(f, "{}", v),
+            CvarValue::Float(v) => write!(f, "{}", v),
+            CvarValue::Str(v) => write!(f, "{}", v),
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+struct Cvar {
+    value: CvarValue,
+    default: CvarValue,
+    description: String,
+    // the lowest and highest a numeric cvar can be set to
+    range: Option<(f64, f64)>,
+}
+
+impl Cvar {
+    fn check_range(&self, value: &CvarValue) -> Result<(), String> {
+        let number = match value {
+            CvarValue::Float(v) => *v as f64,
+            _ => return Ok(()),
+        };
+        match self.range {
+            // written so NaN fails too
+            Some((min, max)) if !(number >= min && number <= max) => Err(format!("{} isn't between {} and {}", value, min, max)),
+            _ => Ok(()),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct Cvars {
+    vars: BTreeMap<String, Cvar>,
+}
+
+impl Cvars {
+    pub fn new() -> Cvars {
+        Cvars::default()
+    }
+
+    pub fn register(&mut self, name: &str, default: CvarValue, description: &str) {
+        self.vars.insert(name.to_string(), Cvar {
+            value: default.clone(),
+            default,
+            description: description.to_string(),
+            range: None,
+        });
+    }
+
+    // limit a numeric cvar to `min..=max`, e.g. so a value that would make the
+    // projection panic can't be set. setting outside it fails, adjusting stops at it.
+    pub fn set_range(&mut self, name: &str, min: f64, max: f64) {
+        match self.vars.get_mut(name) {
+            Some(var) => var.range = Some((min, max)),
+            None => panic!("no cvar named `{}` to limit", name),
+        }
+    }
+
+    pub fn get(&self, name: &str) -> Option<&CvarValue> {
+        self.vars.get(name).map(|v| &v.value)
+    }
+
+    // set from text, which has to parse as the cvar's type
+    pub fn set(&mut self, name: &str, text: &str) -> Result<(), String> {
+        let var = self.vars.get_mut(name).ok_or_else(|| format!("no cvar named `{}`", name))?;
+        let value = var.value.parse_same_type(text)
+            .map_err(|e| format!("`{}` isn't a valid {}: {}", text, var.value.type_name(), e))?;
+        var.check_range(&value).map_err(|e| format!("can't set {}: {}", name, e))?;
+        var.value = value;
+        Ok(())
+    }
+
+    pub fn reset(&mut self, name: &str) -> Result<(), String> {
+        let var = self.vars.get_mut(name).ok_or_else(|| format!("no cvar named `{}`", name))?;
+        var.value = var.default.clone();
+        Ok(())
+    }
+
+    // add `step` to a numeric cvar, or flip a bool one
+    pub fn adjust(&mut self, name: &str, step: f32) -> Result<(), String> {
+        let var = self.vars.get_mut(name).ok_or_else(|| format!("no cvar named `{}`", name))?;
+        let (min, max) = var.range.unwrap_or((f64::MIN, f64::MAX));
+        var.value = match var.value {
+            CvarValue::Bool(v) => CvarValue::Bool(!v),
+            CvarValue::Float(v) => CvarValue::Float(((v + step) as f64).clamp(min, max) as f32),
+            CvarValue::Str(_) => return Err(format!("`{}` isn't numeric", name)),
+        };
+        Ok(())
+    }
+
+    // typed getters, for cvars the code registered itself. asking for an
+    // unregistered cvar, or the wrong type, is a bug.
+    pub fn bool(&self, name: &str) -> bool {
+        match self.get(name) {
+            Some(CvarValue::Bool(v)) => *v,
+            other => panic!("cvar `{}` isn't a bool: {:?}", name, other),
+        }
+    }
+
+    pub fn float(&self, name: &str) -> f32 {
+        match self.get(name) {
+            Some(CvarValue::Float(v)) => *v,
+            other => panic!("cvar `{}` isn't a float: {:?}", name, other),
+        }
+    }
+
+    pub fn string(&self, name: &str) -> &str {
+        match self.get(name) {
+            Some(CvarValue::Str(v)) => v,
+            other => panic!("cvar `{}` isn't a string: {:?}", name, other),
+        }
+    }
+
+    // one line per cvar: name, value, type and description
+    pub fn describe(&self) -> Vec<String> {
+        self.vars.iter()
+            .map(|(name, v)| match v.range {
+                Some((min, max)) => format!("{} = {} ({}, {} to {}) - {}", name, v.value, v.value.type_name(), min, max, v.description),
+                None => format!("{} = {} ({}) - {}", name, v.value, v.value.type_name(), v.description),
+            })
+            .collect()
+    }
+
+    // every value as text, in the form main_1 takes its parameters
+    pub fn to_parameters(&self) -> HashMap<String, String> {
+        self.vars.iter().map(|(name, v)| (name.clone(), v.value.to_string())).collect()
+    }
+
+    // set each of `parameters`, returning a message for each one that couldn't be
+    pub fn apply_parameters(&mut self, parameters: &HashMap<String, String>) -> Vec<String> {
+        parameters.iter()
+            .filter_map(|(name, value)| self.set(name, value).err())
+            .collect()
+    }
+
+    // config files are `name = value` lines. lines starting with # are comments.
+    pub fn load<P: AsRef<std::path::Path>>(&mut self, path: P) -> Result<Vec<String>, Box<dyn std::error::Error>> {
+        let text = std::fs::read_to_string(path)?;
+        let mut problems = Vec::new();
+        for (number, line) in text.lines().enumerate() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+
+            let mut parts = line.splitn(2, '=');
+            let name = parts.next().unwrap_or("").trim();
+            match parts.next() {
+                Some(value) => if let Err(e) = self.set(name, value.trim()) {
+                    problems.push(format!("line {}: {}", number + 1, e));
+                },
+                None => problems.push(format!("line {}: expected `name = value`", number + 1)),
+            }
+        }
+        Ok(problems)
+    }
+
+    pub fn save<P: AsRef<std::path::Path>>(&self, path: P) -> std::io::Result<()> {
+        let mut text = String::new();
+        for (name, v) in self.vars.iter() {
+            text.push_str(&format!("# {}\n{} = {}\n", v.description, name, v.value));
+        }
+        std::fs::write(path, text)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn speed() -> Cvars {
+        let mut cvars = Cvars::new();
+        cvars.register("speed", CvarValue::Float(3.0), "how fast");
+        cvars.set_range("speed", 0.5, 10.0);
+        cvars
+    }
+
+    #[test]
+    fn setting_outside_the_range_fails() {
+        let mut cvars = speed();
+        assert!(cvars.set("speed", "20").is_err());
+        assert!(cvars.set("speed", "NaN").is_err());
+        assert!(cvars.set("speed", "fast").is_err());
+        assert_eq!(cvars.float("speed"), 3.0);
+        assert!(cvars.set("speed", "10").is_ok());
+        assert_eq!(cvars.float("speed"), 10.0);
+    }
+
+    #[test]
+    fn adjusting_stops_at_the_range() {
+        let mut cvars = speed();
+        cvars.adjust("speed", 1.5).unwrap();
+        assert_eq!(cvars.float("speed"), 4.5);
+        cvars.adjust("speed", -100.0).unwrap();
+        assert_eq!(cvars.float("speed"), 0.5);
+    }
+}
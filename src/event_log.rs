@@ -0,0 +1,365 @@
+// records the window events main_1 handles to a file, and plays such a file back
+// in their place, so a session can be repeated exactly, e.g. to check camera
+// movement or restarts in ci. the frame times are recorded too and a replay steps
+// by them, so the camera ends up where it did when recording. mouse motion isn't
+// recorded, so mouse look doesn't replay.
+//
+// a log has a line per event: the seconds since recording started, then one of
+//     key <scancode> <key name, or - if it has none> pressed|released
+//     mouse <button> pressed|released
+//     char <unicode code point>
+//     resized <width> <height>
+//     focused true|false
+//     close
+//     frame <seconds the frame took>
+// where frame ends the events of a frame. lines starting with # are comments.
+//
+// like the tracer, recording and replaying carry on across a restart of main_1.
+//
+// a replay can be given the camera pose it should end with, and fails if the
+// camera ends up somewhere else. its window is hidden but still real, so it needs
+// a display. the events drive the camera through controls::Controls, which
+// doesn't, so the controls tests replay data/turn_and_fly.events without one.
+use crate::input_map::Input;
+use crate::Error;
+use std::io::Write;
+use std::sync::Mutex;
+use std::time::Instant;
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Recorded {
+    Key {
+        scancode: u32,
+        key: Option<glutin::VirtualKeyCode>,
+        pressed: bool,
+    },
+    MouseButton {
+        button: glutin::MouseButton,
+        pressed: bool,
+    },
+    Character(char),
+    // logical size
+    Resized(f64, f64),
+    Focused(bool),
+    CloseRequested,
+}
+
+fn state_name(pressed: bool) -> &'static str {
+    if pressed { "pressed" } else { "released" }
+}
+
+fn parse_state(text: &str) -> Result<bool, String> {
+    match text {
+        "pressed" => Ok(true),
+        "released" => Ok(false),
+        _ => Err(format!("expected pressed or released, not `{}`", text)),
+    }
+}
+
+impl Recorded {
+    // the part of the event worth recording, or None for events that aren't
+    pub fn from_window_event(event: &glutin::WindowEvent) -> Option<Recorded> {
+        use glutin::WindowEvent::*;
+        match event {
+            KeyboardInput{input, ..} => Some(Recorded::Key {
+                scancode: input.scancode,
+                key: input.virtual_keycode,
+                pressed: input.state == glutin::ElementState::Pressed,
+            }),
+            MouseInput{state, button, ..} => Some(Recorded::MouseButton {
+                button: *button,
+                pressed: *state == glutin::ElementState::Pressed,
+            }),
+            ReceivedCharacter(c) => Some(Recorded::Character(*c)),
+            Resized(size) => Some(Recorded::Resized(size.width, size.height)),
+            Focused(focused) => Some(Recorded::Focused(*focused)),
+            CloseRequested => Some(Recorded::CloseRequested),
+            _ => None,
+        }
+    }
+
+    pub fn to_window_event(&self) -> glutin::WindowEvent {
+        // replayed events didn't come from any device
+        let device_id = unsafe { glutin::DeviceId::dummy() };
+        let element_state = |pressed| if pressed { glutin::ElementState::Pressed } else { glutin::ElementState::Released };
+        match *self {
+            Recorded::Key { scancode, key, pressed } => glutin::WindowEvent::KeyboardInput {
+                device_id,
+                input: glutin::KeyboardInput {
+                    scancode,
+                    state: element_state(pressed),
+                    virtual_keycode: key,
+                    modifiers: glutin::ModifiersState::default(),
+                },
+            },
+            Recorded::MouseButton { button, pressed } => glutin::WindowEvent::MouseInput {
+                device_id,
+                state: element_state(pressed),
+                button,
+                modifiers: glutin::ModifiersState::default(),
+            },
+            Recorded::Character(c) => glutin::WindowEvent::ReceivedCharacter(c),
+            Recorded::Resized(width, height) => glutin::WindowEvent::Resized(glutin::dpi::LogicalSize::new(width, height)),
+            Recorded::Focused(focused) => glutin::WindowEvent::Focused(focused),
+            Recorded::CloseRequested => glutin::WindowEvent::CloseRequested,
+        }
+    }
+
+    fn to_line(&self) -> String {
+        match self {
+            Recorded::Key { scancode, key, pressed } => {
+                let name = key.map_or("-".to_string(), |key| Input::Key(key).to_string());
+                format!("key {} {} {}", scancode, name, state_name(*pressed))
+            },
+            Recorded::MouseButton { button, pressed } => format!("mouse {} {}", Input::Mouse(*button), state_name(*pressed)),
+            Recorded::Character(c) => format!("char {}", *c as u32),
+            Recorded::Resized(width, height) => format!("resized {} {}", width, height),
+            Recorded::Focused(focused) => format!("focused {}", focused),
+            Recorded::CloseRequested => "close".to_string(),
+        }
+    }
+
+    fn parse(words: &[&str]) -> Result<Recorded, String> {
+        let number = |text: &str| text.parse::<f64>().map_err(|e| format!("`{}`: {}", text, e));
+        match words {
+            ["key", scancode, name, state] => Ok(Recorded::Key {
+                scancode: scancode.parse().map_err(|_| format!("`{}` isn't a scancode", scancode))?,
+                key: match *name {
+                    "-" => None,
+                    name => match Input::parse(name)? {
+                        Input::Key(key) => Some(key),
+                        _ => return Err(format!("`{}` isn't a key", name)),
+                    },
+                },
+                pressed: parse_state(state)?,
+            }),
+            ["mouse", button, state] => match Input::parse(button)? {
+                Input::Mouse(button) => Ok(Recorded::MouseButton { button, pressed: parse_state(state)? }),
+                _ => Err(format!("`{}` isn't a mouse button", button)),
+            },
+            ["char", code] => code.parse().ok().and_then(std::char::from_u32)
+                .map(Recorded::Character)
+                .ok_or_else(|| format!("`{}` isn't a character code", code)),
+            ["resized", width, height] => Ok(Recorded::Resized(number(width)?, number(height)?)),
+            ["focused", focused] => focused.parse().map(Recorded::Focused).map_err(|_| format!("`{}` isn't true or false", focused)),
+            ["close"] => Ok(Recorded::CloseRequested),
+            _ => Err("unknown event".to_string()),
+        }
+    }
+}
+
+struct Recording {
+    start: Instant,
+    file: std::io::BufWriter<std::fs::File>,
+}
+
+pub struct ReplayFrame {
+    pub events: Vec<Recorded>,
+    pub seconds: f64,
+}
+
+// how far the camera can end up from where it's expected, in units and degrees
+const CAMERA_TOLERANCE: f32 = 0.01;
+
+struct Replay {
+    frames: std::collections::VecDeque<ReplayFrame>,
+    played: u64,
+    // x, y, z, yaw, pitch and roll
+    expected_camera: Option<[f32;6]>,
+}
+
+static RECORDING: Mutex<Option<Recording>> = Mutex::new(None);
+static REPLAY: Mutex<Option<Replay>> = Mutex::new(None);
+
+pub fn start_recording(path: &str) -> std::io::Result<()> {
+    let mut file = std::io::BufWriter::new(std::fs::File::create(path)?);
+    writeln!(file, "# seconds event")?;
+    if let Ok(mut recording) = RECORDING.lock() {
+        *recording = Some(Recording { start: Instant::now(), file });
+    }
+    log::info!("recording events to {}", path);
+    Ok(())
+}
+
+// parses a camera pose written `x,y,z,yaw,pitch,roll`
+pub fn parse_camera(text: &str) -> Result<[f32;6], Error> {
+    let values = text.split(',')
+        .map(|v| v.trim().parse::<f32>())
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(|e| Error::new(format!("`{}`: {}", text, e)))?;
+    if values.len() != 6 {
+        return Err(Error::new(format!("`{}` isn't x,y,z,yaw,pitch,roll", text)));
+    }
+    let mut camera = [0.0;6];
+    camera.copy_from_slice(&values);
+    Ok(camera)
+}
+
+pub fn start_replay(path: &str, expected_camera: Option<[f32;6]>) -> Result<(), Box<dyn std::error::Error>> {
+    let text = std::fs::read_to_string(path)?;
+    let frames = parse_log(&text).map_err(|e| Error::new(format!("{} {}", path, e)))?;
+    log::info!("replaying {} frames from {}", frames.len(), path);
+    if let Ok(mut replay) = REPLAY.lock() {
+        *replay = Some(Replay { frames, played: 0, expected_camera });
+    }
+    Ok(())
+}
+
+// the frames in the text of a log
+pub fn parse_log(text: &str) -> Result<std::collections::VecDeque<ReplayFrame>, String> {
+    let mut frames = std::collections::VecDeque::new();
+    let mut events = Vec::new();
+    for (number, line) in text.lines().enumerate() {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+
+        let words = line.split_whitespace().collect::<Vec<_>>();
+        let result = match words[1..] {
+            ["frame", seconds] => seconds.parse()
+                .map(|seconds| frames.push_back(ReplayFrame { events: std::mem::take(&mut events), seconds }))
+                .map_err(|_| format!("`{}` isn't a number of seconds", seconds)),
+            _ => Recorded::parse(&words[1..]).map(|event| events.push(event)),
+        };
+        result.map_err(|e| format!("line {}: {}", number + 1, e))?;
+    }
+    if !events.is_empty() {
+        log::warn!("ignoring {} events after the last frame", events.len());
+    }
+    Ok(frames)
+}
+
+pub fn is_recording() -> bool {
+    RECORDING.lock().map(|r| r.is_some()).unwrap_or(false)
+}
+
+pub fn is_replaying() -> bool {
+    REPLAY.lock().map(|r| r.is_some()).unwrap_or(false)
+}
+
+pub fn record(event: &glutin::WindowEvent) {
+    if let Some(recorded) = Recorded::from_window_event(event) {
+        write_line(&recorded.to_line());
+    }
+}
+
+// marks the end of a frame's events, with how long the frame took
+pub fn end_frame(seconds: f64) {
+    write_line(&format!("frame {}", seconds));
+    if let Ok(mut recording) = RECORDING.lock() {
+        if let Some(recording) = recording.as_mut() {
+            // so a crash keeps everything up to the last frame
+            let _ = recording.file.flush();
+        }
+    }
+}
+
+fn write_line(line: &str) {
+    if let Ok(mut recording) = RECORDING.lock() {
+        let failed = match recording.as_mut() {
+            Some(recording) => {
+                let seconds = recording.start.elapsed().as_nanos() as f64 / 1000000000.0;
+                writeln!(recording.file, "{:.6} {}", seconds, line).is_err()
+            },
+            None => false,
+        };
+        if failed {
+            log::warn!("couldn't write the event log, stopping recording");
+            *recording = None;
+        }
+    }
+}
+
+// the next frame's events and how long it took, or None once the replay is over
+pub fn next_frame() -> Option<ReplayFrame> {
+    let mut replay = REPLAY.lock().ok()?;
+    let replay = replay.as_mut()?;
+    let frame = replay.frames.pop_front()?;
+    replay.played += 1;
+    Some(frame)
+}
+
+// ends the replay once next_frame has run out, failing if the camera, given as
+// x, y, z, yaw, pitch and roll, isn't where it was expected to be
+pub fn finish_replay(camera: [f32;6]) -> Result<(), Error> {
+    let replay = match REPLAY.lock().ok().and_then(|mut r| r.take()) {
+        Some(replay) => replay,
+        None => return Ok(()),
+    };
+    let pose = |c: &[f32;6]| c.iter().map(|v| format!("{:.4}", v)).collect::<Vec<_>>().join(",");
+    log::info!("replayed {} frames, ending with the camera at {}", replay.played, pose(&camera));
+
+    match replay.expected_camera {
+        Some(expected) if camera.iter().zip(expected.iter()).any(|(a, b)| (a - b).abs() > CAMERA_TOLERANCE) => {
+            Err(Error::new(format!("the replay ended with the camera at {}, expected {}", pose(&camera), pose(&expected))))
+        },
+        Some(_) => {
+            log::info!("the camera ended where it was expected");
+            Ok(())
+        },
+        None => Ok(()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn round_trip(event: Recorded) {
+        let line = event.to_line();
+        let words = line.split_whitespace().collect::<Vec<_>>();
+        assert_eq!(Recorded::parse(&words), Ok(event), "{}", line);
+    }
+
+    #[test]
+    fn every_key_round_trips() {
+        for &key in crate::input_map::KEYS.iter() {
+            round_trip(Recorded::Key { scancode: 58, key: Some(key), pressed: true });
+            round_trip(Recorded::Key { scancode: 58, key: Some(key), pressed: false });
+        }
+        round_trip(Recorded::Key { scancode: 0, key: None, pressed: true });
+    }
+
+    #[test]
+    fn other_events_round_trip() {
+        round_trip(Recorded::MouseButton { button: glutin::MouseButton::Right, pressed: true });
+        round_trip(Recorded::MouseButton { button: glutin::MouseButton::Other(7), pressed: false });
+        round_trip(Recorded::Character('é'));
+        round_trip(Recorded::Resized(800.0, 600.5));
+        round_trip(Recorded::Focused(false));
+        round_trip(Recorded::CloseRequested);
+    }
+
+    // the only test to use the replay state, which is global
+    #[test]
+    fn replays_carry_on_and_check_the_camera() {
+        let path = concat!(env!("CARGO_MANIFEST_DIR"), "/data/turn_and_fly.events");
+        let frames = parse_log(&std::fs::read_to_string(path).unwrap()).unwrap();
+        let expected = [1.0, 2.0, 3.0, 4.0, 5.0, 6.0];
+
+        start_replay(path, Some(expected)).unwrap();
+        assert!(next_frame().is_some());
+        // a restart of main_1 picks up where the replay was
+        assert!(is_replaying());
+        let mut played = 1;
+        while next_frame().is_some() {
+            played += 1;
+        }
+        assert_eq!(played, frames.len());
+
+        assert!(finish_replay([1.0, 2.0, 3.0, 4.0, 5.0, 7.0]).is_err());
+        assert!(!is_replaying());
+
+        start_replay(path, Some(expected)).unwrap();
+        while next_frame().is_some() {}
+        assert!(finish_replay([1.0, 2.0, 3.0, 4.001, 5.0, 6.0]).is_ok());
+    }
+
+    #[test]
+    fn bad_logs_say_where() {
+        assert_eq!(parse_log("0.1 key 30 A pressed\n0.2 frame 0.1\n0.3 key 30 Nope pressed\n").err(),
+            Some("line 3: `Nope` isn't a key name".to_string()));
+        assert_eq!(parse_log("0.1 frame soon\n").err(), Some("line 1: `soon` isn't a number of seconds".to_string()));
+    }
+}
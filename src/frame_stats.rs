@@ -0,0 +1,127 @@
+// rolling frame time history and per-phase cpu timings
+use crate::shape_renderer::{Color, ShapeRenderer};
+use std::collections::VecDeque;
+use std::time::Instant;
+
+const HISTORY_LENGTH: usize = 1000;
+
+// weight given to the newest sample in the per-phase moving averages
+const PHASE_SMOOTHING: f64 = 0.05;
+
+const GRAPH_BACKGROUND: Color = [0.0, 0.0, 0.0, 0.6];
+const GRAPH_LINE: Color = [0.4, 0.9, 0.4, 1.0];
+const GRAPH_TARGET: Color = [0.9, 0.9, 0.3, 0.6];
+
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Summary {
+    pub min: f64,
+    pub average: f64,
+    pub max: f64,
+    // frame times that only the slowest 1% and 0.1% of frames exceed
+    pub percentile_99: f64,
+    pub percentile_99_9: f64,
+}
+
+impl Summary {
+    pub fn fps(&self) -> f64 {
+        if self.average > 0.0 { 1.0 / self.average } else { 0.0 }
+    }
+
+    pub fn low_1_percent_fps(&self) -> f64 {
+        if self.percentile_99 > 0.0 { 1.0 / self.percentile_99 } else { 0.0 }
+    }
+
+    pub fn low_0_1_percent_fps(&self) -> f64 {
+        if self.percentile_99_9 > 0.0 { 1.0 / self.percentile_99_9 } else { 0.0 }
+    }
+}
+
+pub struct FrameStats {
+    // seconds, oldest first
+    frame_times: VecDeque<f64>,
+    // (phase name, average seconds), in the order the phases were first seen
+    phases: Vec<(&'static str, f64)>,
+    phase_start: Instant,
+}
+
+impl FrameStats {
+    pub fn new() -> FrameStats {
+        FrameStats {
+            frame_times: VecDeque::with_capacity(HISTORY_LENGTH),
+            phases: Vec::new(),
+            phase_start: Instant::now(),
+        }
+    }
+
+    pub fn push_frame_time(&mut self, seconds: f64) {
+        if self.frame_times.len() == HISTORY_LENGTH {
+            self.frame_times.pop_front();
+        }
+        self.frame_times.push_back(seconds);
+    }
+
+    pub fn frame_times(&self) -> &VecDeque<f64> {
+        &self.frame_times
+    }
+
+    // start timing the first phase of a frame
+    pub fn begin_phases(&mut self) {
+        self.phase_start = Instant::now();
+    }
+
+    // end the phase that's running and start the next one
+    pub fn end_phase(&mut self, name: &'static str) {
+        let now = Instant::now();
+        let seconds = (now - self.phase_start).as_nanos() as f64 / 1000000000.0;
+        self.phase_start = now;
+
+        match self.phases.iter_mut().find(|(n, _)| *n == name) {
+            Some((_, average)) => *average += (seconds - *average) * PHASE_SMOOTHING,
+            None => self.phases.push((name, seconds)),
+        }
+    }
+
+    pub fn phases(&self) -> &[(&'static str, f64)] {
+        &self.phases
+    }
+
+    pub fn summary(&self) -> Summary {
+        if self.frame_times.is_empty() {
+            return Summary::default();
+        }
+
+        let mut sorted: Vec<f64> = self.frame_times.iter().cloned().collect();
+        sorted.sort_by(|a, b| a.partial_cmp(b).unwrap_or(std::cmp::Ordering::Equal));
+        let percentile = |p: f64| {
+            let index = ((sorted.len() - 1) as f64 * p).round() as usize;
+            sorted[index]
+        };
+
+        Summary {
+            min: sorted[0],
+            average: sorted.iter().sum::<f64>() / sorted.len() as f64,
+            max: sorted[sorted.len() - 1],
+            percentile_99: percentile(0.99),
+            percentile_99_9: percentile(0.999),
+        }
+    }
+
+    // frame time line graph, in window pixels. the horizontal line marks 60 fps.
+    pub fn draw_graph(&self, shapes: &mut ShapeRenderer, x: f32, y: f32, w: f32, h: f32) {
+        shapes.rect(x, y, w, h, GRAPH_BACKGROUND);
+
+        // scale so a 30 fps frame reaches the top, unless something was slower
+        let max = self.frame_times.iter().cloned().fold(1.0 / 30.0, f64::max) as f32;
+        let to_y = |seconds: f32| y + h - (seconds / max).min(1.0) * h;
+
+        let target = to_y(1.0 / 60.0);
+        shapes.line([x, target], [x + w, target], GRAPH_TARGET);
+
+        let step = w / (HISTORY_LENGTH - 1) as f32;
+        let start = x + w - (self.frame_times.len() as f32 - 1.0) * step;
+        let points: Vec<[f32;2]> = self.frame_times.iter().enumerate()
+            .map(|(i, t)| [start + i as f32 * step, to_y(*t as f32)])
+            .collect();
+        shapes.line_strip(&points, GRAPH_LINE);
+    }
+}
@@ -0,0 +1,130 @@
+// values that are the same for every draw in a frame, kept in one uniform buffer
+// so they're uploaded once instead of to each program. any program can use them
+// by declaring the block, which build_program binds to BINDING:
+//
+//     layout (std140) uniform Frame {
+//         mat4 view;
+//         mat4 projection;
+//         mat4 view_projection;
+//         vec3 camera_position;
+//         float time;
+//         vec2 resolution;
+//         float delta_time;
+//         uint frame_index;
+//     };
+use crate::gl;
+use crate::gl_object;
+use crate::Error;
+
+pub const BLOCK_NAME: &str = "Frame";
+pub const BINDING: u32 = 0;
+
+// laid out to match the block under std140. the vec3 takes 12 bytes and the float
+// after it fills out its 16, so there's no padding anywhere.
+#[repr(C)]
+#[derive(Debug, Clone, Copy, Default)]
+pub struct FrameData {
+    pub view: [[f32;4];4],
+    pub projection: [[f32;4];4],
+    pub view_projection: [[f32;4];4],
+    pub camera_position: [f32;3],
+    pub time: f32,
+    // in pixels
+    pub resolution: [f32;2],
+    pub delta_time: f32,
+    pub frame_index: u32,
+}
+
+impl FrameData {
+    // each member's name in the block and its offset in the struct
+    fn offsets() -> [(&'static str, usize);8] {
+        let data = FrameData::default();
+        let base = &data as *const FrameData as usize;
+        let offset = |member: *const u8| member as usize - base;
+        [
+            ("view", offset(&data.view as *const _ as *const u8)),
+            ("projection", offset(&data.projection as *const _ as *const u8)),
+            ("view_projection", offset(&data.view_projection as *const _ as *const u8)),
+            ("camera_position", offset(&data.camera_position as *const _ as *const u8)),
+            ("time", offset(&data.time as *const _ as *const u8)),
+            ("resolution", offset(&data.resolution as *const _ as *const u8)),
+            ("delta_time", offset(&data.delta_time as *const _ as *const u8)),
+            ("frame_index", offset(&data.frame_index as *const _ as *const u8)),
+        ]
+    }
+}
+
+// the buffer behind the block, bound to BINDING for as long as it lives
+pub struct FrameUniforms {
+    buffer: gl_object::Buffer,
+}
+
+impl FrameUniforms {
+    pub fn new() -> FrameUniforms {
+        let buffer = gl_object::Buffer::new();
+        unsafe {
+            gl::BindBuffer(gl::UNIFORM_BUFFER, buffer.id());
+            gl::BufferData(gl::UNIFORM_BUFFER, std::mem::size_of::<FrameData>() as isize,
+                std::ptr::null(), gl::DYNAMIC_DRAW);
+            gl::BindBuffer(gl::UNIFORM_BUFFER, 0);
+            gl::BindBufferBase(gl::UNIFORM_BUFFER, BINDING, buffer.id());
+        }
+        FrameUniforms { buffer }
+    }
+
+    pub fn update(&self, data: &FrameData) {
+        unsafe {
+            gl::BindBuffer(gl::UNIFORM_BUFFER, self.buffer.id());
+            gl::BufferSubData(gl::UNIFORM_BUFFER, 0, std::mem::size_of::<FrameData>() as isize,
+                data as *const FrameData as *const std::os::raw::c_void);
+            gl::BindBuffer(gl::UNIFORM_BUFFER, 0);
+        }
+    }
+}
+
+// point the program's Frame block, if it has one, at BINDING. fails if the
+// program's layout of the block doesn't match FrameData's.
+pub fn bind_block(program: &gl_object::Program) -> Result<(), Error> {
+    let block_name = std::ffi::CString::new(BLOCK_NAME).unwrap();
+    let block_index = unsafe { gl::GetUniformBlockIndex(program.id(), block_name.as_ptr()) };
+    if block_index == gl::INVALID_INDEX {
+        return Ok(());
+    }
+
+    let mut problems = Vec::new();
+    let mut block_size = 0;
+    unsafe {
+        gl::GetActiveUniformBlockiv(program.id(), block_index, gl::UNIFORM_BLOCK_DATA_SIZE, &mut block_size);
+    }
+    if block_size as usize != std::mem::size_of::<FrameData>() {
+        problems.push(format!("the block is {} bytes, FrameData is {}", block_size, std::mem::size_of::<FrameData>()));
+    }
+
+    for &(name, expected) in FrameData::offsets().iter() {
+        // every member of a std140 block counts as active, used or not, so a
+        // missing one means the block was declared differently
+        let cname = std::ffi::CString::new(name).unwrap();
+        let mut index = gl::INVALID_INDEX;
+        let mut offset = 0;
+        unsafe {
+            gl::GetUniformIndices(program.id(), 1, &cname.as_ptr(), &mut index);
+            if index == gl::INVALID_INDEX {
+                problems.push(format!("`{}` isn't in the block", name));
+                continue;
+            }
+            gl::GetActiveUniformsiv(program.id(), 1, &index, gl::UNIFORM_OFFSET, &mut offset);
+        }
+        if offset as usize != expected {
+            problems.push(format!("`{}` is at offset {}, in FrameData it's at {}", name, offset, expected));
+        }
+    }
+
+    if !problems.is_empty() {
+        return Err(Error::new(format!("the {} block doesn't match FrameData: {}", BLOCK_NAME, problems.join(", "))));
+    }
+
+    unsafe {
+        gl::UniformBlockBinding(program.id(), block_index, BINDING);
+    }
+    Ok(())
+}
@@ -0,0 +1,117 @@
+// context creation that falls back through a list of api versions, so the program
+// still starts on drivers that don't offer the newest one
+use crate::Error;
+
+pub const DEFAULT_REQUESTS: &str = "gl4.6, gl4.3, gl3.3, es3.0";
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ContextRequest {
+    pub api: glutin::Api,
+    pub version: (u8, u8),
+    // desktop gl only, es has no profiles
+    pub profile: Option<glutin::GlProfile>,
+}
+
+impl ContextRequest {
+    // "gl4.3" for a desktop core profile context, "gl4.3 compat" for a compatibility
+    // profile one, "es3.0" for gles
+    pub fn parse(text: &str) -> Result<ContextRequest, String> {
+        let text = text.trim();
+        let mut words = text.split_whitespace();
+        let name = words.next().unwrap_or("");
+        let (api, version) = if let Some(version) = name.strip_prefix("gl") {
+            (glutin::Api::OpenGl, version)
+        } else if let Some(version) = name.strip_prefix("es") {
+            (glutin::Api::OpenGlEs, version)
+        } else {
+            return Err(format!("`{}` should start with gl or es", text));
+        };
+
+        let profile = match (api, words.next(), words.next()) {
+            (glutin::Api::OpenGl, None, _) | (glutin::Api::OpenGl, Some("core"), None) => Some(glutin::GlProfile::Core),
+            (glutin::Api::OpenGl, Some("compat"), None) => Some(glutin::GlProfile::Compatibility),
+            (glutin::Api::OpenGlEs, None, _) => None,
+            _ => return Err(format!("`{}` should be a gl version and maybe core or compat, or an es version", text)),
+        };
+
+        let mut parts = version.splitn(2, '.');
+        let major = parts.next().and_then(|v| v.parse().ok());
+        let minor = parts.next().and_then(|v| v.parse().ok());
+        match (major, minor) {
+            (Some(major), Some(minor)) => Ok(ContextRequest { api, version: (major, minor), profile }),
+            _ => Err(format!("`{}` should have a version like 4.3", text)),
+        }
+    }
+
+    pub fn is_es(&self) -> bool {
+        self.api == glutin::Api::OpenGlEs
+    }
+}
+
+impl std::fmt::Display for ContextRequest {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        let api = if self.is_es() { "es" } else { "gl" };
+        write!(f, "{}{}.{}", api, self.version.0, self.version.1)?;
+        if self.profile == Some(glutin::GlProfile::Compatibility) {
+            write!(f, " compat")?;
+        }
+        Ok(())
+    }
+}
+
+// parse a comma separated list of requests, returning a message for each one that couldn't be
+pub fn parse_requests(text: &str) -> (Vec<ContextRequest>, Vec<String>) {
+    let mut problems = Vec::new();
+    let requests = text.split(',')
+        .filter(|r| !r.trim().is_empty())
+        .filter_map(|r| ContextRequest::parse(r).map_err(|e| problems.push(e)).ok())
+        .collect();
+    (requests, problems)
+}
+
+// create a window with the first context in `requests` the driver will give us,
+// returning which one it was
+pub fn create(window: &glutin::WindowBuilder, vsync: bool, requests: &[ContextRequest], ev_loop: &glutin::EventsLoop)
+    -> Result<(glutin::GlWindow, ContextRequest), Error>
+{
+    let mut failures = Vec::new();
+    for request in requests {
+        let mut context = glutin::ContextBuilder::new()
+            .with_gl(glutin::GlRequest::Specific(request.api, request.version))
+            .with_vsync(vsync);
+        if let Some(profile) = request.profile {
+            context = context.with_gl_profile(profile);
+        }
+
+        match glutin::GlWindow::new(window.clone(), context, ev_loop) {
+            Ok(gl_window) => return Ok((gl_window, *request)),
+            Err(e) => {
+                log::info!("couldn't create a {} context: {}", request, e);
+                failures.push(format!("{}: {}", request, e));
+            },
+        }
+    }
+
+    Err(Error::new(format!("couldn't create any of the requested gl contexts\n{}", failures.join("\n"))))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn requests_choose_a_profile() {
+        let parse = |text| ContextRequest::parse(text).map(|r| r.profile);
+        assert_eq!(parse("gl4.3"), Ok(Some(glutin::GlProfile::Core)));
+        assert_eq!(parse("gl4.3 core"), Ok(Some(glutin::GlProfile::Core)));
+        assert_eq!(parse(" gl3.3  compat "), Ok(Some(glutin::GlProfile::Compatibility)));
+        assert_eq!(parse("es3.0"), Ok(None));
+        assert!(parse("es3.0 compat").is_err());
+        assert!(parse("gl3.3 compat core").is_err());
+
+        let (requests, problems) = parse_requests("gl4.6, gl3.3 compat, es3.0");
+        assert!(problems.is_empty());
+        let names: Vec<String> = requests.iter().map(|r| r.to_string()).collect();
+        assert_eq!(names, ["gl4.6", "gl3.3 compat", "es3.0"]);
+    }
+}
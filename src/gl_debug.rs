@@ -0,0 +1,254 @@
+// reports messages from gl's debug output. messages are filtered by severity and id,
+// and a message that keeps repeating is only printed once, with a count of the
+// repeats printed now and then.
+use crate::cvars::Cvars;
+use crate::gl;
+use std::collections::HashMap;
+use std::os::raw::{c_char, c_void};
+use std::sync::Mutex;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Severity {
+    Notification,
+    Low,
+    Medium,
+    High,
+}
+
+impl Severity {
+    fn from_gl(severity: u32) -> Severity {
+        match severity {
+            gl::DEBUG_SEVERITY_HIGH => Severity::High,
+            gl::DEBUG_SEVERITY_MEDIUM => Severity::Medium,
+            gl::DEBUG_SEVERITY_LOW => Severity::Low,
+            _ => Severity::Notification,
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<Severity> {
+        match name {
+            "notification" => Some(Severity::Notification),
+            "low" => Some(Severity::Low),
+            "medium" => Some(Severity::Medium),
+            "high" => Some(Severity::High),
+            _ => None,
+        }
+    }
+
+    // the log level messages of this severity are reported at
+    fn log_level(self) -> log::Level {
+        match self {
+            Severity::High => log::Level::Error,
+            Severity::Medium => log::Level::Warn,
+            Severity::Low => log::Level::Info,
+            Severity::Notification => log::Level::Debug,
+        }
+    }
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Severity::Notification => "notification",
+            Severity::Low => "low",
+            Severity::Medium => "medium",
+            Severity::High => "high",
+        }
+    }
+}
+
+fn source_name(source: u32) -> &'static str {
+    match source {
+        gl::DEBUG_SOURCE_API => "api",
+        gl::DEBUG_SOURCE_WINDOW_SYSTEM => "window system",
+        gl::DEBUG_SOURCE_SHADER_COMPILER => "shader compiler",
+        gl::DEBUG_SOURCE_THIRD_PARTY => "third party",
+        gl::DEBUG_SOURCE_APPLICATION => "application",
+        _ => "other source",
+    }
+}
+
+fn type_name(ty: u32) -> &'static str {
+    match ty {
+        gl::DEBUG_TYPE_ERROR => "error",
+        gl::DEBUG_TYPE_DEPRECATED_BEHAVIOR => "deprecated behavior",
+        gl::DEBUG_TYPE_UNDEFINED_BEHAVIOR => "undefined behavior",
+        gl::DEBUG_TYPE_PORTABILITY => "portability",
+        gl::DEBUG_TYPE_PERFORMANCE => "performance",
+        gl::DEBUG_TYPE_MARKER => "marker",
+        gl::DEBUG_TYPE_PUSH_GROUP => "push group",
+        gl::DEBUG_TYPE_POP_GROUP => "pop group",
+        _ => "other",
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Settings {
+    // messages less severe than this are dropped
+    pub min_severity: Severity,
+    pub ignored_ids: Vec<u32>,
+    // stop on high severity errors, so a debugger or the backtrace shows the call
+    // that caused them. only debug builds do this.
+    pub break_on_error: bool,
+}
+
+impl Settings {
+    // from the gl_debug_* cvars, returning a message for each value that couldn't be used
+    pub fn from_cvars(cvars: &Cvars) -> (Settings, Vec<String>) {
+        let mut problems = Vec::new();
+
+        let severity = cvars.string("gl_debug_severity");
+        let min_severity = Severity::from_name(severity).unwrap_or_else(|| {
+            problems.push(format!("unknown gl debug severity `{}`", severity));
+            Severity::Low
+        });
+
+        let ignored_ids = cvars.string("gl_debug_ignore_ids").split(',')
+            .map(|id| id.trim())
+            .filter(|id| !id.is_empty())
+            .filter_map(|id| match parse_id(id) {
+                Some(id) => Some(id),
+                None => {
+                    problems.push(format!("gl debug message id `{}` isn't a number", id));
+                    None
+                },
+            })
+            .collect();
+
+        let settings = Settings {
+            min_severity,
+            ignored_ids,
+            break_on_error: cvars.bool("gl_debug_break"),
+        };
+        (settings, problems)
+    }
+}
+
+// ids are usually written in hex by drivers' own tools
+fn parse_id(text: &str) -> Option<u32> {
+    if let Some(hex) = text.strip_prefix("0x") {
+        u32::from_str_radix(hex, 16).ok()
+    } else {
+        text.parse().ok()
+    }
+}
+
+struct Seen {
+    severity: Severity,
+    count: u64,
+    reported: u64,
+    description: String,
+}
+
+struct State {
+    settings: Settings,
+    // keyed by (source, type, id, message). drivers reuse ids like 0 for
+    // unrelated messages, so the id alone doesn't tell messages apart.
+    seen: HashMap<(u32, u32, u32, String), Seen>,
+}
+
+// owns the state the debug callback reads. it has to stay alive, and at the same
+// address, for as long as the context can call back, so it's boxed and turns
+// debug output off again when dropped.
+pub struct DebugOutput {
+    state: Box<Mutex<State>>,
+}
+
+impl DebugOutput {
+    pub fn install(settings: Settings) -> DebugOutput {
+        let synchronous = settings.break_on_error;
+        let state = Box::new(Mutex::new(State { settings, seen: HashMap::new() }));
+        unsafe {
+            gl::Enable(gl::DEBUG_OUTPUT);
+            // a break is only useful from the thread that made the call
+            if cfg!(debug_assertions) && synchronous {
+                gl::Enable(gl::DEBUG_OUTPUT_SYNCHRONOUS);
+            }
+            let user_param = &*state as *const Mutex<State> as *mut c_void;
+            gl::DebugMessageCallback(callback, user_param);
+        }
+        DebugOutput { state }
+    }
+
+    pub fn settings(&self) -> Option<Settings> {
+        self.state.lock().ok().map(|state| state.settings.clone())
+    }
+
+    pub fn set_settings(&self, settings: Settings) {
+        unsafe {
+            if cfg!(debug_assertions) && settings.break_on_error {
+                gl::Enable(gl::DEBUG_OUTPUT_SYNCHRONOUS);
+            } else {
+                gl::Disable(gl::DEBUG_OUTPUT_SYNCHRONOUS);
+            }
+        }
+        if let Ok(mut state) = self.state.lock() {
+            state.settings = settings;
+        }
+    }
+
+    // print how many times each message repeated since it was last reported
+    pub fn report_repeats(&self) {
+        if let Ok(mut state) = self.state.lock() {
+            for seen in state.seen.values_mut() {
+                if seen.count > seen.reported {
+                    log::log!(seen.severity.log_level(), "{} (repeated {} more times)",
+                        seen.description, seen.count - seen.reported);
+                    seen.reported = seen.count;
+                }
+            }
+        }
+    }
+}
+
+impl Drop for DebugOutput {
+    fn drop(&mut self) {
+        unsafe {
+            gl::Disable(gl::DEBUG_OUTPUT);
+        }
+    }
+}
+
+extern "system" fn callback(
+    source: u32, ty: u32, id: u32, severity: u32, length: i32,
+    message: *const c_char, user_param: *mut c_void)
+{
+    let state = unsafe { &*(user_param as *const Mutex<State>) };
+    let mut state = match state.lock() {
+        Ok(state) => state,
+        Err(_) => return,
+    };
+
+    let severity = Severity::from_gl(severity);
+    if severity < state.settings.min_severity || state.settings.ignored_ids.contains(&id) {
+        return;
+    }
+
+    // a negative length means the message is null terminated
+    let message = unsafe {
+        if length < 0 {
+            std::ffi::CStr::from_ptr(message).to_string_lossy().into_owned()
+        } else {
+            let bytes = std::slice::from_raw_parts(message as *const u8, length as usize);
+            String::from_utf8_lossy(bytes).into_owned()
+        }
+    };
+    let message = message.trim_end().to_string();
+
+    let description = format!("gl {} {} from {} (id 0x{:x}): {}",
+        severity.name(), type_name(ty), source_name(source), id, message);
+    let seen = state.seen.entry((source, ty, id, message)).or_insert(Seen { severity, count: 0, reported: 0, description });
+    seen.count += 1;
+    if seen.count > 1 {
+        return;
+    }
+    seen.reported = 1;
+    log::log!(severity.log_level(), "{}", seen.description);
+
+    // unwinding out of a callback gl called isn't allowed, so print where we are and abort
+    if cfg!(debug_assertions) && state.settings.break_on_error
+        && severity == Severity::High && ty == gl::DEBUG_TYPE_ERROR
+    {
+        log::error!("{}", std::backtrace::Backtrace::force_capture());
+        log::logger().flush();
+        std::process::abort();
+    }
+}
@@ -0,0 +1,164 @@
+// what the current context is and what it can do, queried once at startup so
+// optional features can be turned on only where they're supported
+use crate::gl;
+
+// from EXT_texture_filter_anisotropic, core since gl 4.6
+pub const TEXTURE_MAX_ANISOTROPY: u32 = 0x84FE;
+const MAX_TEXTURE_MAX_ANISOTROPY: u32 = 0x84FF;
+
+#[derive(Debug, Clone)]
+pub struct GlInfo {
+    pub vendor: String,
+    pub renderer: String,
+    pub version: String,
+    pub glsl_version: String,
+    pub major: i32,
+    pub minor: i32,
+    pub es: bool,
+    pub extensions: Vec<String>,
+    pub max_texture_size: i32,
+    // None if anisotropic filtering isn't supported
+    pub max_anisotropy: Option<f32>,
+    pub max_samples: i32,
+    pub max_uniform_block_size: i32,
+}
+
+fn string(name: u32) -> String {
+    unsafe {
+        let ptr = gl::GetString(name);
+        if ptr.is_null() {
+            String::new()
+        } else {
+            std::ffi::CStr::from_ptr(ptr as *const std::os::raw::c_char).to_string_lossy().into_owned()
+        }
+    }
+}
+
+fn integer(name: u32) -> i32 {
+    let mut value = 0;
+    unsafe {
+        gl::GetIntegerv(name, &mut value);
+    }
+    value
+}
+
+impl GlInfo {
+    // query the context that's current
+    pub fn probe() -> GlInfo {
+        let version = string(gl::VERSION);
+
+        let extensions = (0..integer(gl::NUM_EXTENSIONS).max(0) as u32)
+            .filter_map(|index| unsafe {
+                let ptr = gl::GetStringi(gl::EXTENSIONS, index);
+                if ptr.is_null() {
+                    None
+                } else {
+                    Some(std::ffi::CStr::from_ptr(ptr as *const std::os::raw::c_char).to_string_lossy().into_owned())
+                }
+            })
+            .collect::<Vec<_>>();
+
+        let mut info = GlInfo {
+            vendor: string(gl::VENDOR),
+            renderer: string(gl::RENDERER),
+            glsl_version: string(gl::SHADING_LANGUAGE_VERSION),
+            major: integer(gl::MAJOR_VERSION),
+            minor: integer(gl::MINOR_VERSION),
+            es: version.starts_with("OpenGL ES"),
+            version,
+            extensions,
+            max_texture_size: integer(gl::MAX_TEXTURE_SIZE),
+            max_anisotropy: None,
+            max_samples: integer(gl::MAX_SAMPLES),
+            max_uniform_block_size: integer(gl::MAX_UNIFORM_BLOCK_SIZE),
+        };
+
+        if info.at_least(4, 6) || info.has_extension("GL_EXT_texture_filter_anisotropic")
+            || info.has_extension("GL_ARB_texture_filter_anisotropic")
+        {
+            let mut value = 0.0;
+            unsafe {
+                gl::GetFloatv(MAX_TEXTURE_MAX_ANISOTROPY, &mut value);
+            }
+            // at least 2 where it's supported, so anything less is a failed query
+            if value >= 1.0 {
+                info.max_anisotropy = Some(value);
+            }
+        }
+
+        info
+    }
+
+    pub fn at_least(&self, major: i32, minor: i32) -> bool {
+        (self.major, self.minor) >= (major, minor)
+    }
+
+    pub fn has_extension(&self, name: &str) -> bool {
+        self.extensions.iter().any(|e| e == name)
+    }
+
+    pub fn supports_debug_output(&self) -> bool {
+        (!self.es && self.at_least(4, 3)) || (self.es && self.at_least(3, 2)) || self.has_extension("GL_KHR_debug")
+    }
+
+    pub fn supports_timer_queries(&self) -> bool {
+        (!self.es && self.at_least(3, 3)) || self.has_extension("GL_ARB_timer_query")
+    }
+
+    // needed for reversed-z depth. gles only has it as an extension with its own entry point.
+    pub fn supports_clip_control(&self) -> bool {
+        (!self.es && self.at_least(4, 5)) || self.has_extension("GL_ARB_clip_control")
+    }
+
+    pub fn log(&self) {
+        log::info!("gl vendor: {}", self.vendor);
+        log::info!("gl renderer: {}", self.renderer);
+        log::info!("gl version: {}", self.version);
+        log::info!("glsl version: {}", self.glsl_version);
+        log::info!("max texture size {}, max samples {}, max uniform block size {}",
+            self.max_texture_size, self.max_samples, self.max_uniform_block_size);
+        match self.max_anisotropy {
+            Some(anisotropy) => log::info!("max anisotropy {}", anisotropy),
+            None => log::info!("anisotropic filtering not supported"),
+        }
+        log::debug!("{} extensions: {}", self.extensions.len(), self.extensions.join(" "));
+    }
+
+    pub fn to_json(&self) -> String {
+        let extensions = self.extensions.iter()
+            .map(|e| format!("\"{}\"", escape(e)))
+            .collect::<Vec<_>>()
+            .join(", ");
+        let anisotropy = self.max_anisotropy.map_or("null".to_string(), |a| a.to_string());
+
+        let mut json = String::from("{\n");
+        json.push_str(&format!("  \"vendor\": \"{}\",\n", escape(&self.vendor)));
+        json.push_str(&format!("  \"renderer\": \"{}\",\n", escape(&self.renderer)));
+        json.push_str(&format!("  \"version\": \"{}\",\n", escape(&self.version)));
+        json.push_str(&format!("  \"glsl_version\": \"{}\",\n", escape(&self.glsl_version)));
+        json.push_str(&format!("  \"major\": {},\n  \"minor\": {},\n  \"es\": {},\n", self.major, self.minor, self.es));
+        json.push_str(&format!("  \"max_texture_size\": {},\n", self.max_texture_size));
+        json.push_str(&format!("  \"max_anisotropy\": {},\n", anisotropy));
+        json.push_str(&format!("  \"max_samples\": {},\n", self.max_samples));
+        json.push_str(&format!("  \"max_uniform_block_size\": {},\n", self.max_uniform_block_size));
+        json.push_str(&format!("  \"debug_output\": {},\n", self.supports_debug_output()));
+        json.push_str(&format!("  \"timer_queries\": {},\n", self.supports_timer_queries()));
+        json.push_str(&format!("  \"clip_control\": {},\n", self.supports_clip_control()));
+        json.push_str(&format!("  \"extensions\": [{}]\n", extensions));
+        json.push('}');
+        json
+    }
+}
+
+fn escape(text: &str) -> String {
+    let mut escaped = String::new();
+    for c in text.chars() {
+        match c {
+            '"' => escaped.push_str("\\\""),
+            '\\' => escaped.push_str("\\\\"),
+            c if (c as u32) < 0x20 => escaped.push_str(&format!("\\u{:04x}", c as u32)),
+            c => escaped.push(c),
+        }
+    }
+    escaped
+}
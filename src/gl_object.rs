@@ -0,0 +1,112 @@
+// owned handles to gl objects, which delete the object they hold when dropped.
+// in debug builds every live handle is tracked, so that anything still alive when
+// the context goes away can be reported as a leak.
+use crate::gl;
+
+macro_rules! gl_object {
+    ($name:ident, $kind:expr, |$id:ident| $delete:expr) => {
+        #[derive(Debug)]
+        pub struct $name(u32);
+
+        impl $name {
+            // take ownership of an object created elsewhere, e.g. by gl_helpers
+            pub fn from_raw(id: u32) -> $name {
+                track($kind, id);
+                $name(id)
+            }
+
+            pub fn id(&self) -> u32 {
+                self.0
+            }
+        }
+
+        impl Drop for $name {
+            fn drop(&mut self) {
+                untrack($kind, self.0);
+                let $id = self.0;
+                unsafe {
+                    $delete;
+                }
+            }
+        }
+    };
+}
+
+gl_object!(Program, "program", |id| gl::DeleteProgram(id));
+gl_object!(Buffer, "buffer", |id| gl::DeleteBuffers(1, &id));
+gl_object!(VertexArray, "vertex array", |id| gl::DeleteVertexArrays(1, &id));
+gl_object!(Texture, "texture", |id| gl::DeleteTextures(1, &id));
+gl_object!(Query, "query", |id| gl::DeleteQueries(1, &id));
+
+impl Buffer {
+    pub fn new() -> Buffer {
+        let mut id = 0;
+        unsafe {
+            gl::GenBuffers(1, &mut id);
+        }
+        Buffer::from_raw(id)
+    }
+}
+
+impl Texture {
+    pub fn new() -> Texture {
+        let mut id = 0;
+        unsafe {
+            gl::GenTextures(1, &mut id);
+        }
+        Texture::from_raw(id)
+    }
+}
+
+impl Query {
+    pub fn new() -> Query {
+        let mut id = 0;
+        unsafe {
+            gl::GenQueries(1, &mut id);
+        }
+        Query::from_raw(id)
+    }
+}
+
+#[cfg(debug_assertions)]
+static LIVE_OBJECTS: std::sync::Mutex<Vec<(&str, u32)>> = std::sync::Mutex::new(Vec::new());
+
+#[cfg(debug_assertions)]
+fn track(kind: &'static str, id: u32) {
+    if let Ok(mut live) = LIVE_OBJECTS.lock() {
+        live.push((kind, id));
+    }
+}
+
+#[cfg(debug_assertions)]
+fn untrack(kind: &'static str, id: u32) {
+    if let Ok(mut live) = LIVE_OBJECTS.lock() {
+        if let Some(index) = live.iter().position(|e| *e == (kind, id)) {
+            live.swap_remove(index);
+        }
+    }
+}
+
+#[cfg(not(debug_assertions))]
+fn track(_kind: &'static str, _id: u32) {}
+
+#[cfg(not(debug_assertions))]
+fn untrack(_kind: &'static str, _id: u32) {}
+
+// create this right after the context is made current, so it's dropped just before the
+// context is. any handle still alive at that point would outlive its context.
+pub struct LeakCheck;
+
+impl Drop for LeakCheck {
+    fn drop(&mut self) {
+        #[cfg(debug_assertions)]
+        {
+            if let Ok(mut live) = LIVE_OBJECTS.lock() {
+                for (kind, id) in live.iter() {
+                    log::warn!("leaked gl {} {}", kind, id);
+                }
+                live.clear();
+            }
+        }
+    }
+}
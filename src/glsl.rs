@@ -0,0 +1,107 @@
+// picks a glsl version the current context understands, so built-in shaders
+// can be written once without a #version line and compiled against it
+use crate::frame_uniforms;
+use crate::gl;
+use crate::gl_object;
+use crate::Error;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum GlslVersion {
+    Glsl430,
+    Glsl330,
+    Essl300,
+}
+
+impl GlslVersion {
+    // the lines that have to come before the rest of the shader code
+    pub fn header(self) -> &'static str {
+        match self {
+            GlslVersion::Glsl430 => "#version 430 core\n",
+            GlslVersion::Glsl330 => "#version 330 core\n",
+            GlslVersion::Essl300 => "#version 300 es\nprecision mediump float;\n",
+        }
+    }
+
+    // versions worth trying on the current context, best first
+    pub fn candidates() -> Vec<GlslVersion> {
+        let version = unsafe {
+            let ptr = gl::GetString(gl::SHADING_LANGUAGE_VERSION);
+            if ptr.is_null() {
+                String::new()
+            } else {
+                std::ffi::CStr::from_ptr(ptr as *const std::os::raw::c_char).to_string_lossy().into_owned()
+            }
+        };
+
+        if version.contains(" ES ") || version.starts_with("OpenGL ES") {
+            return vec![GlslVersion::Essl300];
+        }
+
+        // "4.60 NVIDIA", "3.30 - Build ...", etc. if it can't be parsed, just try everything.
+        let number = version.split_whitespace().next()
+            .and_then(|v| v.parse::<f32>().ok())
+            .map_or(u32::MAX, |v| (v * 100.0).round() as u32);
+
+        let mut candidates = Vec::new();
+        if number >= 430 {
+            candidates.push(GlslVersion::Glsl430);
+        }
+        if number >= 330 {
+            candidates.push(GlslVersion::Glsl330);
+        }
+        candidates.push(GlslVersion::Essl300);
+        candidates
+    }
+}
+
+// code with any #version line it starts with taken out, so another can be put in
+fn without_version_line(code: &str) -> &str {
+    let trimmed = code.trim_start();
+    if trimmed.starts_with("#version") {
+        trimmed.find('\n').map_or("", |end| &trimmed[end + 1..])
+    } else {
+        code
+    }
+}
+
+// build a program from shader code, trying each candidate version in turn. a
+// #version line already in the code is replaced by the candidate's. a Frame
+// block in the code is bound to the shared per-frame uniform buffer.
+pub fn build_program(name: &str, vertex_code: &str, fragment_code: &str) -> Result<(gl_object::Program, GlslVersion), Error> {
+    let vertex_code = without_version_line(vertex_code);
+    let fragment_code = without_version_line(fragment_code);
+    let mut failures = Vec::new();
+    for version in GlslVersion::candidates() {
+        let vcode = format!("{}{}", version.header(), vertex_code);
+        let fcode = format!("{}{}", version.header(), fragment_code);
+        let result = gl_helpers::ProgramBuilder::new()
+            .vertex_shader_code(&vcode)
+            .fragment_shader_code(&fcode)
+            .build();
+
+        match result {
+            Ok(program) => {
+                log::debug!("built {} shaders as {:?}", name, version);
+                let program = gl_object::Program::from_raw(program);
+                frame_uniforms::bind_block(&program)
+                    .map_err(|e| Error::new(format!("{} shaders: {}", name, e)))?;
+                return Ok((program, version));
+            },
+            Err(e) => {
+                log::debug!("couldn't build {} shaders as {:?}: {}", name, version, e);
+                failures.push(format!("{:?}: {}", version, e));
+            },
+        }
+    }
+
+    Err(Error::new(format!("couldn't build {} shaders with any supported glsl version\n{}",
+        name, failures.join("\n"))))
+}
+
+// look up a uniform that the program can't work without
+pub fn uniform_location(program: &gl_object::Program, name: &str) -> Result<i32, Error> {
+    let cname = std::ffi::CString::new(name)
+        .map_err(|_| Error::new(format!("bad uniform name `{}`", name)))?;
+    gl_helpers::get_uniform_location(program.id(), cname.as_ptr())
+        .map_err(|e| Error::new(format!("uniform `{}` not found: {}", name, e)))
+}
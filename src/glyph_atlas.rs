@@ -0,0 +1,193 @@
+use crate::gl;
+use crate::gl_object;
+use rusttype::{point, Font, Scale};
+
+// printable ascii range that gets rasterized into the atlas
+const FIRST_CHAR: u8 = b' ';
+const LAST_CHAR: u8 = b'~';
+const FALLBACK_CHAR: u8 = b'?';
+
+const ATLAS_WIDTH: i32 = 512;
+const GLYPH_PADDING: i32 = 1;
+
+// glyph quad in text units, relative to the pen position on the baseline.
+// one text unit is the font's ascent, so glyphs line up with the stroke font.
+#[derive(Debug, Clone, Copy, Default)]
+struct Glyph {
+    min: [f32;2],
+    max: [f32;2],
+    uv_min: [f32;2],
+    uv_max: [f32;2],
+    advance: f32,
+    visible: bool,
+}
+
+// single channel texture holding one bitmap per printable ascii character,
+// along with the metrics needed to lay out strings of them
+pub struct GlyphAtlas {
+    font: Font<'static>,
+    scale: Scale,
+    units_per_pixel: f32,
+    glyphs: Vec<Glyph>,
+    width: i32,
+    height: i32,
+    pixels: Vec<u8>,
+}
+
+impl GlyphAtlas {
+    // rasterize every glyph at pixel_height with `border` empty pixels around it.
+    // the bitmap handed to `process` has dimensions that are multiples of `downsample`,
+    // and it must return a bitmap `downsample` times smaller in each dimension.
+    pub fn new<F>(font: Font<'static>, pixel_height: f32, border: i32, downsample: i32, process: F) -> GlyphAtlas
+        where F: Fn(&[u8], i32, i32) -> Vec<u8>
+    {
+        let scale = Scale::uniform(pixel_height);
+        let units_per_pixel = 1.0 / font.v_metrics(scale).ascent;
+
+        // rasterize every glyph, packing them into rows of the atlas as we go
+        let mut glyphs = Vec::new();
+        let mut bitmaps = Vec::new();
+        let (mut pen_x, mut pen_y, mut row_height) = (GLYPH_PADDING, GLYPH_PADDING, 0);
+        for c in (FIRST_CHAR..=LAST_CHAR).map(char::from) {
+            let glyph = font.glyph(c).scaled(scale);
+            let advance = glyph.h_metrics().advance_width * units_per_pixel;
+            let glyph = glyph.positioned(point(0.0, 0.0));
+
+            let bb = match glyph.pixel_bounding_box() {
+                Some(bb) => bb,
+                None => {
+                    glyphs.push(Glyph { advance, ..Glyph::default() });
+                    continue;
+                }
+            };
+
+            let round_up = |n: i32| (n + downsample - 1) / downsample * downsample;
+            let (raster_w, raster_h) = (round_up(bb.width() + border * 2), round_up(bb.height() + border * 2));
+            let mut raster = vec![0u8; (raster_w * raster_h) as usize];
+            glyph.draw(|x, y, v| {
+                let x = x as i32 + border;
+                let y = y as i32 + border;
+                raster[(y * raster_w + x) as usize] = (v * 255.0).round() as u8;
+            });
+
+            let pixels = process(&raster, raster_w, raster_h);
+            let (w, h) = (raster_w / downsample, raster_h / downsample);
+
+            if pen_x + w + GLYPH_PADDING > ATLAS_WIDTH {
+                pen_x = GLYPH_PADDING;
+                pen_y += row_height + GLYPH_PADDING;
+                row_height = 0;
+            }
+            bitmaps.push((pen_x, pen_y, w, h, pixels));
+
+            // rusttype's y axis points down, ours points up
+            let x0 = (bb.min.x - border) as f32;
+            let y1 = (bb.min.y - border) as f32;
+            glyphs.push(Glyph {
+                min: [x0 * units_per_pixel, -(y1 + raster_h as f32) * units_per_pixel],
+                max: [(x0 + raster_w as f32) * units_per_pixel, -y1 * units_per_pixel],
+                uv_min: [pen_x as f32, (pen_y + h) as f32],
+                uv_max: [(pen_x + w) as f32, pen_y as f32],
+                advance,
+                visible: true,
+            });
+
+            pen_x += w + GLYPH_PADDING;
+            row_height = row_height.max(h);
+        }
+
+        let width = ATLAS_WIDTH;
+        let height = pen_y + row_height + GLYPH_PADDING;
+        let mut pixels = vec![0u8; (width * height) as usize];
+        for (x0, y0, w, h, bitmap) in bitmaps.iter() {
+            for y in 0..*h {
+                let src = (y * w) as usize;
+                let dst = ((y0 + y) * width + x0) as usize;
+                pixels[dst..dst + *w as usize].copy_from_slice(&bitmap[src..src + *w as usize]);
+            }
+        }
+
+        for g in glyphs.iter_mut().filter(|g| g.visible) {
+            g.uv_min[0] /= width as f32;
+            g.uv_max[0] /= width as f32;
+            g.uv_min[1] /= height as f32;
+            g.uv_max[1] /= height as f32;
+        }
+
+        GlyphAtlas { font, scale, units_per_pixel, glyphs, width, height, pixels }
+    }
+
+    // size of one rasterized pixel in text units, before any downsampling
+    pub fn units_per_pixel(&self) -> f32 {
+        self.units_per_pixel
+    }
+
+    pub fn create_texture(&self) -> gl_object::Texture {
+        let texture = gl_object::Texture::new();
+        unsafe {
+            gl::BindTexture(gl::TEXTURE_2D, texture.id());
+            gl::PixelStorei(gl::UNPACK_ALIGNMENT, 1);
+            gl::TexImage2D(gl::TEXTURE_2D, 0, gl::R8 as i32, self.width, self.height,
+                0, gl::RED, gl::UNSIGNED_BYTE, self.pixels.as_ptr() as *const std::os::raw::c_void);
+            gl::PixelStorei(gl::UNPACK_ALIGNMENT, 4);
+            gl::TexParameteri(gl::TEXTURE_2D, gl::TEXTURE_MIN_FILTER, gl::LINEAR as i32);
+            gl::TexParameteri(gl::TEXTURE_2D, gl::TEXTURE_MAG_FILTER, gl::LINEAR as i32);
+            gl::TexParameteri(gl::TEXTURE_2D, gl::TEXTURE_WRAP_S, gl::CLAMP_TO_EDGE as i32);
+            gl::TexParameteri(gl::TEXTURE_2D, gl::TEXTURE_WRAP_T, gl::CLAMP_TO_EDGE as i32);
+        }
+        texture
+    }
+
+    fn glyph_index(c: char) -> usize {
+        let c = if c.is_ascii() { c as u8 } else { FALLBACK_CHAR };
+        let c = if (FIRST_CHAR..=LAST_CHAR).contains(&c) { c } else { FALLBACK_CHAR };
+        (c - FIRST_CHAR) as usize
+    }
+
+    // horizontal distance the pen moves while laying out `string`, in text units
+    pub fn measure(&self, string: &str) -> f32 {
+        let mut width = 0.0;
+        let mut previous = None;
+        for c in string.chars() {
+            let index = GlyphAtlas::glyph_index(c);
+            let c = char::from(FIRST_CHAR + index as u8);
+            if let Some(previous) = previous {
+                width += self.font.pair_kerning(self.scale, previous, c) * self.units_per_pixel;
+            }
+            previous = Some(c);
+            width += self.glyphs[index].advance;
+        }
+        width
+    }
+
+    // two triangles per visible glyph, laid out in text units,
+    // interleaved as position (2 floats) then texture coordinates (2 floats)
+    pub fn build_vertices(&self, string: &str) -> Vec<f32> {
+        let mut vertices = Vec::with_capacity(string.len() * 24);
+        let mut pen_x = 0.0;
+        let mut previous = None;
+        for c in string.chars() {
+            let index = GlyphAtlas::glyph_index(c);
+            let c = char::from(FIRST_CHAR + index as u8);
+
+            if let Some(previous) = previous {
+                pen_x += self.font.pair_kerning(self.scale, previous, c) * self.units_per_pixel;
+            }
+            previous = Some(c);
+
+            let g = &self.glyphs[index];
+            if g.visible {
+                let (x0, x1) = (pen_x + g.min[0], pen_x + g.max[0]);
+                let (y0, y1) = (g.min[1], g.max[1]);
+                let (u0, v0, u1, v1) = (g.uv_min[0], g.uv_min[1], g.uv_max[0], g.uv_max[1]);
+                vertices.extend_from_slice(&[
+                    x0, y0, u0, v0,   x1, y0, u1, v0,   x1, y1, u1, v1,
+                    x0, y0, u0, v0,   x1, y1, u1, v1,   x0, y1, u0, v1,
+                ]);
+            }
+
+            pen_x += g.advance;
+        }
+        vertices
+    }
+}
@@ -0,0 +1,178 @@
+// gpu time spent on each render pass, measured with timestamp queries.
+// results are read back a few frames late so that waiting on them never stalls
+// the pipeline.
+use crate::gl;
+use crate::gl_object;
+use std::cell::RefCell;
+use std::collections::VecDeque;
+
+// frames that can have queries in flight. a frame's results are read when its
+// slot comes around again.
+const FRAMES_IN_FLIGHT: usize = 4;
+
+// weight given to the newest sample in the per-pass moving averages
+const PASS_SMOOTHING: f64 = 0.05;
+
+// rows kept for csv export
+const CSV_HISTORY_LENGTH: usize = 10000;
+
+struct Pass {
+    name: &'static str,
+    start: gl_object::Query,
+    end: gl_object::Query,
+}
+
+#[derive(Default)]
+struct Frame {
+    // queries are reused from frame to frame, `used` of them belong to this one
+    passes: Vec<Pass>,
+    used: usize,
+    number: u64,
+    pending: bool,
+}
+
+#[derive(Default)]
+struct State {
+    frames: Vec<Frame>,
+    current: usize,
+    frame_number: u64,
+    // (pass name, average seconds), in the order the passes were first seen
+    averages: Vec<(&'static str, f64)>,
+    // (frame number, pass name, seconds)
+    rows: VecDeque<(u64, &'static str, f64)>,
+    // frames whose results weren't ready in time and were thrown away
+    dropped: u64,
+}
+
+pub struct GpuTimer {
+    supported: bool,
+    state: RefCell<State>,
+}
+
+// times everything drawn while it's alive
+pub struct Scope<'a> {
+    _timer: &'a GpuTimer,
+    query: Option<u32>,
+}
+
+impl<'a> Drop for Scope<'a> {
+    fn drop(&mut self) {
+        if let Some(query) = self.query {
+            unsafe {
+                gl::QueryCounter(query, gl::TIMESTAMP);
+            }
+        }
+    }
+}
+
+impl GpuTimer {
+    // `supported` says whether the context has timestamp queries. without them
+    // scopes do nothing.
+    pub fn new(supported: bool) -> GpuTimer {
+        let mut state = State::default();
+        state.frames.resize_with(FRAMES_IN_FLIGHT, Frame::default);
+        GpuTimer { supported, state: RefCell::new(state) }
+    }
+
+    pub fn is_supported(&self) -> bool {
+        self.supported
+    }
+
+    // start timing a pass. it ends when the returned scope is dropped.
+    pub fn scope(&self, name: &'static str) -> Scope<'_> {
+        if !self.supported {
+            return Scope { _timer: self, query: None };
+        }
+
+        let mut state = self.state.borrow_mut();
+        let current = state.current;
+        let frame = &mut state.frames[current];
+        if frame.used == frame.passes.len() {
+            frame.passes.push(Pass {
+                name,
+                start: gl_object::Query::new(),
+                end: gl_object::Query::new(),
+            });
+        }
+        let pass = &mut frame.passes[frame.used];
+        frame.used += 1;
+        pass.name = name;
+        unsafe {
+            gl::QueryCounter(pass.start.id(), gl::TIMESTAMP);
+        }
+        Scope { _timer: self, query: Some(pass.end.id()) }
+    }
+
+    // call once per frame, after the last pass. collects the results of the oldest
+    // frame in flight, if they're ready.
+    pub fn end_frame(&self) {
+        if !self.supported {
+            return;
+        }
+
+        let mut state = self.state.borrow_mut();
+        let state = &mut *state;
+        {
+            let frame = &mut state.frames[state.current];
+            frame.number = state.frame_number;
+            frame.pending = frame.used > 0;
+        }
+        state.frame_number += 1;
+        state.current = (state.current + 1) % FRAMES_IN_FLIGHT;
+
+        let frame = &mut state.frames[state.current];
+        if frame.pending {
+            // queries finish in order, so the last one being ready means they all are
+            let mut available = 0;
+            unsafe {
+                gl::GetQueryObjectiv(frame.passes[frame.used - 1].end.id(), gl::QUERY_RESULT_AVAILABLE, &mut available);
+            }
+
+            if available != 0 {
+                for pass in frame.passes[..frame.used].iter() {
+                    let (mut start, mut end) = (0, 0);
+                    unsafe {
+                        gl::GetQueryObjectui64v(pass.start.id(), gl::QUERY_RESULT, &mut start);
+                        gl::GetQueryObjectui64v(pass.end.id(), gl::QUERY_RESULT, &mut end);
+                    }
+                    let seconds = end.saturating_sub(start) as f64 / 1000000000.0;
+
+                    match state.averages.iter_mut().find(|(n, _)| *n == pass.name) {
+                        Some((_, average)) => *average += (seconds - *average) * PASS_SMOOTHING,
+                        None => state.averages.push((pass.name, seconds)),
+                    }
+
+                    if state.rows.len() == CSV_HISTORY_LENGTH {
+                        state.rows.pop_front();
+                    }
+                    state.rows.push_back((frame.number, pass.name, seconds));
+                }
+            } else {
+                state.dropped += 1;
+            }
+        }
+        frame.pending = false;
+        frame.used = 0;
+    }
+
+    // (pass name, average seconds) for each pass timed so far
+    pub fn passes(&self) -> Vec<(&'static str, f64)> {
+        self.state.borrow().averages.clone()
+    }
+
+    pub fn dropped_frames(&self) -> u64 {
+        self.state.borrow().dropped
+    }
+
+    // write the recent per-pass timings as `frame,pass,milliseconds` rows,
+    // returning how many rows were written
+    pub fn write_csv<P: AsRef<std::path::Path>>(&self, path: P) -> std::io::Result<usize> {
+        let state = self.state.borrow();
+        let mut text = String::from("frame,pass,milliseconds\n");
+        for (frame, name, seconds) in state.rows.iter() {
+            text.push_str(&format!("{},{},{:.4}\n", frame, name, seconds * 1000.0));
+        }
+        std::fs::write(path, text)?;
+        Ok(state.rows.len())
+    }
+}
@@ -0,0 +1,426 @@
+// named actions, like move_forward or screenshot, bound to keys, mouse buttons and
+// gamepad buttons and sticks. the code asks about actions rather than particular
+// keys, so the bindings can be changed from the input config file or the console.
+//
+// the config file has `action = input, input` lines, replacing the default
+// bindings of that action. an input is a key name such as W or LShift,
+// scancode:<number> for a key by where it is on the keyboard whatever the layout,
+// mouse:left, mouse:right, mouse:middle or mouse:<number>, gamepad:<button> such
+// as gamepad:south or gamepad:dpadup, or a stick pushed one way, such as
+// gamepad:leftstickx+ or gamepad:rightsticky-.
+// lines starting with # are comments.
+use crate::key_states::KeyStates;
+use std::collections::{BTreeMap, HashSet};
+
+// every action, with its default bindings
+pub const DEFAULT_BINDINGS: [(&str, &str);32] = [
+    ("move_forward", "W, gamepad:leftsticky+"),
+    ("move_back", "S, gamepad:leftsticky-"),
+    ("strafe_left", "Q, gamepad:leftstickx-"),
+    ("strafe_right", "E, gamepad:leftstickx+"),
+    ("move_up", "R, gamepad:righttrigger"),
+    ("move_down", "F, gamepad:lefttrigger"),
+    ("turn_left", "A, gamepad:rightstickx-"),
+    ("turn_right", "D, gamepad:rightstickx+"),
+    ("look_up", "Up, gamepad:rightsticky+"),
+    ("look_down", "Down, gamepad:rightsticky-"),
+    ("roll_left", "Z, gamepad:lefttrigger2"),
+    ("roll_right", "X, gamepad:righttrigger2"),
+    ("sprint", "LShift, RShift, gamepad:leftthumb"),
+    ("slow", "LControl, RControl"),
+    ("mouse_look", "mouse:right"),
+    ("release_mouse", "Escape"),
+    ("toggle_orbit", "O, gamepad:north"),
+    ("frame_scene", "Home, gamepad:west"),
+    ("next_text_renderer", "T"),
+    ("next_projection", "P"),
+    ("next_label_mode", "L"),
+    ("toggle_inspector", "F1, gamepad:select"),
+    ("toggle_frame_stats", "F3"),
+    ("toggle_vsync", "V"),
+    ("thinner_text", "LBracket"),
+    ("bolder_text", "RBracket"),
+    ("narrower_fov", "PageDown"),
+    ("wider_fov", "PageUp"),
+    ("slower_camera", "Minus"),
+    ("faster_camera", "Equals"),
+    ("screenshot", "F12"),
+    ("quit", "F10"),
+];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Input {
+    Key(glutin::VirtualKeyCode),
+    Scancode(u32),
+    Mouse(glutin::MouseButton),
+    GamepadButton(gilrs::Button),
+    // an axis past STICK_PRESSED, in the positive direction if true
+    GamepadStick(gilrs::Axis, bool),
+}
+
+// how far a stick has to be pushed to count as pressed
+const STICK_PRESSED: f32 = 0.5;
+
+const GAMEPAD_BUTTONS: &[(gilrs::Button, &str)] = &[
+    (gilrs::Button::South, "south"),
+    (gilrs::Button::East, "east"),
+    (gilrs::Button::North, "north"),
+    (gilrs::Button::West, "west"),
+    (gilrs::Button::C, "c"),
+    (gilrs::Button::Z, "z"),
+    (gilrs::Button::LeftTrigger, "lefttrigger"),
+    (gilrs::Button::LeftTrigger2, "lefttrigger2"),
+    (gilrs::Button::RightTrigger, "righttrigger"),
+    (gilrs::Button::RightTrigger2, "righttrigger2"),
+    (gilrs::Button::Select, "select"),
+    (gilrs::Button::Start, "start"),
+    (gilrs::Button::Mode, "mode"),
+    (gilrs::Button::LeftThumb, "leftthumb"),
+    (gilrs::Button::RightThumb, "rightthumb"),
+    (gilrs::Button::DPadUp, "dpadup"),
+    (gilrs::Button::DPadDown, "dpaddown"),
+    (gilrs::Button::DPadLeft, "dpadleft"),
+    (gilrs::Button::DPadRight, "dpadright"),
+];
+
+const GAMEPAD_STICKS: &[(gilrs::Axis, &str)] = &[
+    (gilrs::Axis::LeftStickX, "leftstickx"),
+    (gilrs::Axis::LeftStickY, "leftsticky"),
+    (gilrs::Axis::RightStickX, "rightstickx"),
+    (gilrs::Axis::RightStickY, "rightsticky"),
+];
+
+macro_rules! key_names {
+    ($($key:ident),*) => {
+        // every VirtualKeyCode in the order they're declared, so a key is at the
+        // index of its value, with the name of each
+        pub const KEYS: &[glutin::VirtualKeyCode] = &[$(glutin::VirtualKeyCode::$key),*];
+        const KEY_NAMES: &[&str] = &[$(stringify!($key)),*];
+    };
+}
+
+key_names!(
+    Key1, Key2, Key3, Key4, Key5, Key6, Key7, Key8, Key9, Key0,
+    A, B, C, D, E, F, G, H, I, J, K, L, M, N, O, P, Q, R, S, T, U, V, W, X, Y, Z,
+    Escape, F1, F2, F3, F4, F5, F6, F7, F8, F9, F10, F11, F12,
+    F13, F14, F15, F16, F17, F18, F19, F20, F21, F22, F23, F24,
+    Snapshot, Scroll, Pause, Insert, Home, Delete, End, PageDown, PageUp,
+    Left, Up, Right, Down, Back, Return, Space, Compose, Caret, Numlock,
+    Numpad0, Numpad1, Numpad2, Numpad3, Numpad4, Numpad5, Numpad6, Numpad7, Numpad8, Numpad9,
+    AbntC1, AbntC2, Add, Apostrophe, Apps, At, Ax, Backslash, Calculator, Capital, Colon,
+    Comma, Convert, Decimal, Divide, Equals, Grave, Kana, Kanji, LAlt, LBracket, LControl,
+    LShift, LWin, Mail, MediaSelect, MediaStop, Minus, Multiply, Mute, MyComputer,
+    NavigateForward, NavigateBackward, NextTrack, NoConvert, NumpadComma, NumpadEnter,
+    NumpadEquals, OEM102, Period, PlayPause, Power, PrevTrack, RAlt, RBracket, RControl,
+    RShift, RWin, Semicolon, Slash, Sleep, Stop, Subtract, Sysrq, Tab, Underline, Unlabeled,
+    VolumeDown, VolumeUp, Wake, WebBack, WebFavorites, WebForward, WebHome, WebRefresh,
+    WebSearch, WebStop, Yen, Copy, Paste, Cut
+);
+
+// the key by the name of its VirtualKeyCode, ignoring case
+fn key_from_name(name: &str) -> Option<glutin::VirtualKeyCode> {
+    KEY_NAMES.iter().position(|n| n.eq_ignore_ascii_case(name)).map(|i| KEYS[i])
+}
+
+impl Input {
+    pub fn parse(text: &str) -> Result<Input, String> {
+        let text = text.trim();
+        let lower = text.to_ascii_lowercase();
+        if let Some(button) = lower.strip_prefix("mouse:") {
+            return match button {
+                "left" => Ok(Input::Mouse(glutin::MouseButton::Left)),
+                "right" => Ok(Input::Mouse(glutin::MouseButton::Right)),
+                "middle" => Ok(Input::Mouse(glutin::MouseButton::Middle)),
+                number => number.parse().map(|n| Input::Mouse(glutin::MouseButton::Other(n)))
+                    .map_err(|_| format!("`{}` isn't a mouse button", text)),
+            };
+        }
+        if let Some(scancode) = lower.strip_prefix("scancode:") {
+            return scancode.parse().map(Input::Scancode)
+                .map_err(|_| format!("`{}` isn't a scancode", text));
+        }
+        if let Some(name) = lower.strip_prefix("gamepad:") {
+            let stick = |sign: char, positive: bool| {
+                let name = name.strip_suffix(sign)?;
+                GAMEPAD_STICKS.iter().find(|&&(_, n)| n == name).map(|&(axis, _)| Input::GamepadStick(axis, positive))
+            };
+            return GAMEPAD_BUTTONS.iter().find(|&&(_, n)| n == name).map(|&(button, _)| Input::GamepadButton(button))
+                .or_else(|| stick('+', true))
+                .or_else(|| stick('-', false))
+                .ok_or_else(|| format!("`{}` isn't a gamepad button or stick", text));
+        }
+        key_from_name(text).map(Input::Key).ok_or_else(|| format!("`{}` isn't a key name", text))
+    }
+}
+
+impl std::fmt::Display for Input {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self {
+            Input::Key(key) => write!(f, "{:?}", key),
+            Input::Scancode(n) => write!(f, "scancode:{}", n),
+            Input::Mouse(glutin::MouseButton::Left) => write!(f, "mouse:left"),
+            Input::Mouse(glutin::MouseButton::Right) => write!(f, "mouse:right"),
+            Input::Mouse(glutin::MouseButton::Middle) => write!(f, "mouse:middle"),
+            Input::Mouse(glutin::MouseButton::Other(n)) => write!(f, "mouse:{}", n),
+            Input::GamepadButton(button) => {
+                let name = GAMEPAD_BUTTONS.iter().find(|&&(b, _)| b == *button).map_or("unknown", |&(_, n)| n);
+                write!(f, "gamepad:{}", name)
+            },
+            Input::GamepadStick(axis, positive) => {
+                let name = GAMEPAD_STICKS.iter().find(|&&(a, _)| a == *axis).map_or("unknown", |&(_, n)| n);
+                write!(f, "gamepad:{}{}", name, if *positive { '+' } else { '-' })
+            },
+        }
+    }
+}
+
+// a comma separated list of inputs. an empty list unbinds.
+pub fn parse_inputs(text: &str) -> Result<Vec<Input>, String> {
+    text.split(',')
+        .filter(|i| !i.trim().is_empty())
+        .map(Input::parse)
+        .collect()
+}
+
+pub struct InputMap {
+    bindings: BTreeMap<String, Vec<Input>>,
+    keys: KeyStates,
+    // mouse and gamepad buttons held down, and those that went down or up since
+    // the last new_frame. KeyStates keeps the same for keys.
+    buttons: HashSet<Input>,
+    went_down: HashSet<Input>,
+    went_up: HashSet<Input>,
+}
+
+impl InputMap {
+    pub fn new() -> InputMap {
+        let mut bindings = BTreeMap::new();
+        for &(action, inputs) in DEFAULT_BINDINGS.iter() {
+            bindings.insert(action.to_string(), parse_inputs(inputs).expect("bad default binding"));
+        }
+        InputMap {
+            bindings,
+            keys: KeyStates::new(),
+            buttons: HashSet::new(),
+            went_down: HashSet::new(),
+            went_up: HashSet::new(),
+        }
+    }
+
+    // forget which inputs changed, ready for the next frame's events
+    pub fn new_frame(&mut self) {
+        self.keys.new_frame();
+        self.went_down.clear();
+        self.went_up.clear();
+    }
+
+    pub fn handle_event(&mut self, event: &glutin::WindowEvent) {
+        match event {
+            glutin::WindowEvent::KeyboardInput{input, ..} => self.keys.handle_input(input),
+            glutin::WindowEvent::MouseInput{state, button, ..} => {
+                self.set_input(Input::Mouse(*button), *state == glutin::ElementState::Pressed);
+            },
+            _ => (),
+        }
+    }
+
+    // every connected gamepad counts as the same one
+    pub fn handle_gamepad_event(&mut self, event: &gilrs::EventType) {
+        match *event {
+            gilrs::EventType::ButtonPressed(button, _) => self.set_input(Input::GamepadButton(button), true),
+            gilrs::EventType::ButtonReleased(button, _) => self.set_input(Input::GamepadButton(button), false),
+            gilrs::EventType::AxisChanged(axis, value, _) => self.set_stick(axis, value),
+            gilrs::EventType::Disconnected => {
+                let held: Vec<Input> = self.buttons.iter().cloned()
+                    .filter(|input| matches!(input, Input::GamepadButton(_) | Input::GamepadStick(..)))
+                    .collect();
+                for input in held {
+                    self.set_input(input, false);
+                }
+            },
+            _ => (),
+        }
+    }
+
+    // a stick counts as pressed in a direction once it's pushed far enough that way
+    pub fn set_stick(&mut self, axis: gilrs::Axis, value: f32) {
+        self.set_input(Input::GamepadStick(axis, true), value > STICK_PRESSED);
+        self.set_input(Input::GamepadStick(axis, false), value < -STICK_PRESSED);
+    }
+
+    pub fn set_input(&mut self, input: Input, pressed: bool) {
+        match input {
+            Input::Key(key) => self.keys.set(key, pressed),
+            Input::Scancode(scancode) => self.keys.set_scancode(scancode, pressed),
+            _ if pressed => {
+                if self.buttons.insert(input) {
+                    self.went_down.insert(input);
+                }
+            },
+            _ => {
+                if self.buttons.remove(&input) {
+                    self.went_up.insert(input);
+                }
+            },
+        }
+    }
+
+    // KeyStates::clear, for buttons as well as keys
+    pub fn release_all(&mut self) {
+        self.keys.clear();
+        for input in self.buttons.drain() {
+            self.went_up.insert(input);
+        }
+    }
+
+    fn input_pressed(&self, input: Input) -> bool {
+        match input {
+            Input::Key(key) => self.keys.held(key),
+            Input::Scancode(scancode) => self.keys.scancode_held(scancode),
+            _ => self.buttons.contains(&input),
+        }
+    }
+
+    fn input_went_down(&self, input: Input) -> bool {
+        match input {
+            Input::Key(key) => self.keys.just_pressed(key),
+            Input::Scancode(scancode) => self.keys.scancode_just_pressed(scancode),
+            _ => self.went_down.contains(&input),
+        }
+    }
+
+    fn input_went_up(&self, input: Input) -> bool {
+        match input {
+            Input::Key(key) => self.keys.just_released(key),
+            Input::Scancode(scancode) => self.keys.scancode_just_released(scancode),
+            _ => self.went_up.contains(&input),
+        }
+    }
+
+    // asking about an action that doesn't exist is a bug
+    fn inputs(&self, action: &str) -> &[Input] {
+        match self.bindings.get(action) {
+            Some(inputs) => inputs,
+            None => panic!("no action named `{}`", action),
+        }
+    }
+
+    // whether any input bound to the action is held
+    pub fn pressed(&self, action: &str) -> bool {
+        self.inputs(action).iter().any(|&input| self.input_pressed(input))
+    }
+
+    // whether an input bound to the action went down this frame
+    pub fn just_pressed(&self, action: &str) -> bool {
+        self.inputs(action).iter().any(|&input| self.input_went_down(input))
+    }
+
+    // whether the action stopped being held this frame
+    #[allow(dead_code)]
+    pub fn just_released(&self, action: &str) -> bool {
+        !self.pressed(action) && self.inputs(action).iter().any(|&input| self.input_went_up(input))
+    }
+
+    // 1 or -1 if one of two opposing actions is held, 0 if neither or both are
+    pub fn axis(&self, positive: &str, negative: &str) -> f32 {
+        let value = |action| if self.pressed(action) { 1.0 } else { 0.0 };
+        value(positive) - value(negative)
+    }
+
+    pub fn bind(&mut self, action: &str, inputs: Vec<Input>) -> Result<(), String> {
+        match self.bindings.get_mut(action) {
+            Some(bound) => {
+                *bound = inputs;
+                Ok(())
+            },
+            None => Err(format!("no action named `{}`", action)),
+        }
+    }
+
+    // the inputs bound to an action, as they'd be written in the config file
+    pub fn binding(&self, action: &str) -> Option<String> {
+        self.bindings.get(action)
+            .map(|inputs| inputs.iter().map(|i| i.to_string()).collect::<Vec<_>>().join(", "))
+    }
+
+    // one line per action
+    pub fn describe(&self) -> Vec<String> {
+        self.bindings.keys()
+            .map(|action| format!("{} = {}", action, self.binding(action).unwrap_or_default()))
+            .collect()
+    }
+
+    // returns a message for each line that couldn't be used. lines for actions
+    // that don't exist are skipped rather than failing the whole file.
+    pub fn load<P: AsRef<std::path::Path>>(&mut self, path: P) -> Result<Vec<String>, Box<dyn std::error::Error>> {
+        let text = std::fs::read_to_string(path)?;
+        let mut problems = Vec::new();
+        for (number, line) in text.lines().enumerate() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+
+            let mut parts = line.splitn(2, '=');
+            let action = parts.next().unwrap_or("").trim();
+            let result = match parts.next() {
+                Some(inputs) => parse_inputs(inputs).and_then(|inputs| self.bind(action, inputs)),
+                None => Err("expected `action = inputs`".to_string()),
+            };
+            if let Err(e) = result {
+                problems.push(format!("line {}: {}", number + 1, e));
+            }
+        }
+        Ok(problems)
+    }
+
+    pub fn save<P: AsRef<std::path::Path>>(&self, path: P) -> std::io::Result<()> {
+        let mut text = String::from("# action = inputs\n");
+        for line in self.describe() {
+            text.push_str(&line);
+            text.push('\n');
+        }
+        std::fs::write(path, text)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn every_key_has_a_name() {
+        assert_eq!(KEYS.len(), glutin::VirtualKeyCode::Cut as usize + 1);
+        for (index, &key) in KEYS.iter().enumerate() {
+            assert_eq!(key as usize, index);
+            assert_eq!(Input::parse(&Input::Key(key).to_string()), Ok(Input::Key(key)));
+        }
+    }
+
+    #[test]
+    fn gamepad_inputs_parse_back() {
+        let inputs = GAMEPAD_BUTTONS.iter().map(|&(button, _)| Input::GamepadButton(button))
+            .chain(GAMEPAD_STICKS.iter().map(|&(axis, _)| Input::GamepadStick(axis, true)))
+            .chain(GAMEPAD_STICKS.iter().map(|&(axis, _)| Input::GamepadStick(axis, false)));
+        for input in inputs {
+            assert_eq!(Input::parse(&input.to_string()), Ok(input));
+        }
+        assert_eq!(Input::parse("Gamepad:LeftStickY+"), Ok(Input::GamepadStick(gilrs::Axis::LeftStickY, true)));
+        assert!(Input::parse("gamepad:leftsticky").is_err());
+        assert!(Input::parse("gamepad:nope").is_err());
+    }
+
+    #[test]
+    fn sticks_press_past_halfway() {
+        let mut input_map = InputMap::new();
+        input_map.set_stick(gilrs::Axis::LeftStickY, 0.4);
+        assert!(!input_map.pressed("move_forward"));
+        input_map.set_stick(gilrs::Axis::LeftStickY, 0.9);
+        assert!(input_map.just_pressed("move_forward") && !input_map.pressed("move_back"));
+
+        input_map.new_frame();
+        input_map.set_stick(gilrs::Axis::LeftStickY, -0.9);
+        assert!(input_map.just_released("move_forward") && input_map.just_pressed("move_back"));
+        assert_eq!(input_map.axis("move_forward", "move_back"), -1.0);
+    }
+}
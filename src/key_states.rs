@@ -0,0 +1,207 @@
+// which keys are held, and which went down or up since the last frame. keys are
+// tracked both by the key they mean in the current layout and by scancode, which
+// is where they are on the keyboard whatever the layout.
+use std::collections::HashSet;
+
+// Cut is the last VirtualKeyCode. a key past it, from a newer glutin, reads as
+// never held rather than indexing out of bounds.
+const KEY_COUNT: usize = glutin::VirtualKeyCode::Cut as usize + 1;
+
+static NOT_HELD: bool = false;
+
+pub struct KeyStates {
+    held: [bool;KEY_COUNT],
+    went_down: [bool;KEY_COUNT],
+    went_up: [bool;KEY_COUNT],
+    held_scancodes: HashSet<u32>,
+    scancodes_down: HashSet<u32>,
+    scancodes_up: HashSet<u32>,
+}
+
+impl KeyStates {
+    pub fn new() -> KeyStates {
+        KeyStates {
+            held: [false;KEY_COUNT],
+            went_down: [false;KEY_COUNT],
+            went_up: [false;KEY_COUNT],
+            held_scancodes: HashSet::new(),
+            scancodes_down: HashSet::new(),
+            scancodes_up: HashSet::new(),
+        }
+    }
+
+    // forget which keys went down or up, ready for the next frame's events
+    pub fn new_frame(&mut self) {
+        self.went_down = [false;KEY_COUNT];
+        self.went_up = [false;KEY_COUNT];
+        self.scancodes_down.clear();
+        self.scancodes_up.clear();
+    }
+
+    pub fn handle_input(&mut self, input: &glutin::KeyboardInput) {
+        let pressed = input.state == glutin::ElementState::Pressed;
+        if let Some(key) = input.virtual_keycode {
+            self.set(key, pressed);
+        }
+        self.set_scancode(input.scancode, pressed);
+    }
+
+    // key repeat sends more presses without releases in between, which don't
+    // count as going down again
+    pub fn set(&mut self, key: glutin::VirtualKeyCode, pressed: bool) {
+        let index = key as usize;
+        if index >= KEY_COUNT {
+            return;
+        }
+        if pressed && !self.held[index] {
+            self.went_down[index] = true;
+        } else if !pressed && self.held[index] {
+            self.went_up[index] = true;
+        }
+        self.held[index] = pressed;
+    }
+
+    pub fn set_scancode(&mut self, scancode: u32, pressed: bool) {
+        if pressed {
+            if self.held_scancodes.insert(scancode) {
+                self.scancodes_down.insert(scancode);
+            }
+        } else if self.held_scancodes.remove(&scancode) {
+            self.scancodes_up.insert(scancode);
+        }
+    }
+
+    // let go of every key, e.g. when the window loses focus and won't see the
+    // releases. they count as released this frame.
+    pub fn clear(&mut self) {
+        for (held, went_up) in self.held.iter_mut().zip(self.went_up.iter_mut()) {
+            if *held {
+                *held = false;
+                *went_up = true;
+            }
+        }
+        for scancode in self.held_scancodes.drain() {
+            self.scancodes_up.insert(scancode);
+        }
+    }
+
+    pub fn held(&self, key: glutin::VirtualKeyCode) -> bool {
+        self[key]
+    }
+
+    pub fn just_pressed(&self, key: glutin::VirtualKeyCode) -> bool {
+        self.went_down.get(key as usize).cloned().unwrap_or(false)
+    }
+
+    pub fn just_released(&self, key: glutin::VirtualKeyCode) -> bool {
+        self.went_up.get(key as usize).cloned().unwrap_or(false)
+    }
+
+    pub fn scancode_held(&self, scancode: u32) -> bool {
+        self.held_scancodes.contains(&scancode)
+    }
+
+    pub fn scancode_just_pressed(&self, scancode: u32) -> bool {
+        self.scancodes_down.contains(&scancode)
+    }
+
+    pub fn scancode_just_released(&self, scancode: u32) -> bool {
+        self.scancodes_up.contains(&scancode)
+    }
+}
+
+impl std::ops::Index<glutin::VirtualKeyCode> for KeyStates {
+    type Output = bool;
+    fn index(&self, key: glutin::VirtualKeyCode) -> &bool {
+        self.held.get(key as usize).unwrap_or(&NOT_HELD)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use glutin::VirtualKeyCode::{A, Cut, W};
+
+    #[test]
+    fn a_press_goes_down_once() {
+        let mut keys = KeyStates::new();
+        keys.set(W, true);
+        assert!(keys.held(W) && keys.just_pressed(W));
+
+        // key repeat
+        keys.new_frame();
+        keys.set(W, true);
+        keys.set(W, true);
+        assert!(keys.held(W));
+        assert!(!keys.just_pressed(W));
+
+        keys.new_frame();
+        keys.set(W, false);
+        assert!(!keys.held(W) && keys.just_released(W) && !keys.just_pressed(W));
+
+        keys.new_frame();
+        assert!(!keys.just_released(W));
+    }
+
+    #[test]
+    fn a_tap_within_a_frame_counts_both_ways() {
+        let mut keys = KeyStates::new();
+        keys.set(A, true);
+        keys.set(A, false);
+        assert!(!keys.held(A) && keys.just_pressed(A) && keys.just_released(A));
+    }
+
+    #[test]
+    fn releasing_an_unheld_key_does_nothing() {
+        let mut keys = KeyStates::new();
+        keys.set(A, false);
+        keys.set_scancode(30, false);
+        assert!(!keys.just_released(A) && !keys.scancode_just_released(30));
+    }
+
+    #[test]
+    fn scancodes_go_down_once() {
+        let mut keys = KeyStates::new();
+        keys.set_scancode(30, true);
+        assert!(keys.scancode_held(30) && keys.scancode_just_pressed(30));
+
+        keys.new_frame();
+        keys.set_scancode(30, true);
+        assert!(keys.scancode_held(30) && !keys.scancode_just_pressed(30));
+
+        keys.new_frame();
+        keys.set_scancode(30, false);
+        assert!(!keys.scancode_held(30) && keys.scancode_just_released(30));
+    }
+
+    #[test]
+    fn clear_releases_everything_held() {
+        let mut keys = KeyStates::new();
+        keys.set(W, true);
+        keys.set_scancode(17, true);
+        keys.new_frame();
+
+        keys.clear();
+        assert!(!keys.held(W) && keys.just_released(W));
+        assert!(!keys.scancode_held(17) && keys.scancode_just_released(17));
+        // keys that weren't held aren't released
+        assert!(!keys.just_released(A));
+    }
+
+    // a key past KEY_COUNT can't be made without a newer glutin, so check that
+    // every key there is fits and is tracked on its own
+    #[test]
+    fn every_key_fits() {
+        let mut keys = KeyStates::new();
+        for &key in crate::input_map::KEYS.iter() {
+            assert!((key as usize) < KEY_COUNT);
+            keys.set(key, true);
+            assert!(keys[key] && keys.just_pressed(key));
+        }
+        assert!(keys.held(Cut));
+
+        keys.new_frame();
+        keys.set(A, false);
+        assert!(!keys.held(A) && keys.held(W));
+    }
+}
@@ -0,0 +1,75 @@
+use crate::text_renderer::TextRenderer;
+use cgmath::{EuclideanSpace, Matrix4, Point3, SquareMatrix, Vector3, Vector4};
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum LabelSize {
+    // constant size on screen, given as the height of a capital letter in pixels
+    Pixels(f32),
+    // height of a capital letter in world units, so labels shrink with distance
+    World(f32),
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct LabelOptions {
+    pub size: LabelSize,
+    // turn world sized labels to face the camera. pixel sized labels always face it.
+    pub billboard: bool,
+    // center the label horizontally on its anchor instead of starting there
+    pub centered: bool,
+}
+
+impl Default for LabelOptions {
+    fn default() -> LabelOptions {
+        LabelOptions {
+            size: LabelSize::Pixels(16.0),
+            billboard: true,
+            centered: true,
+        }
+    }
+}
+
+// draw `text` anchored to a position in world space, using the same view and
+// projection matrices as the scene so that labels are hidden behind geometry.
+// labels whose anchor is behind the camera are skipped.
+pub fn draw_label(renderer: &dyn TextRenderer, text: &str, position: Point3<f32>,
+    view: &Matrix4<f32>, projection: &Matrix4<f32>, window_size: (u32, u32), options: &LabelOptions)
+{
+    let view_projection = projection * view;
+    let clip = view_projection * Vector4::new(position.x, position.y, position.z, 1.0);
+    if clip.w <= 0.0 {
+        return;
+    }
+
+    let x_offset = if options.centered { -0.5 * renderer.measure(text) } else { 0.0 };
+    let layout = Matrix4::from_translation(Vector3::new(x_offset, 0.0, 0.0));
+
+    let transform = match options.size {
+        LabelSize::Pixels(pixels) => {
+            if window_size.0 == 0 || window_size.1 == 0 {
+                return;
+            }
+            let ndc = clip.truncate() / clip.w;
+            let xscale = 2.0 * pixels / window_size.0 as f32;
+            let yscale = 2.0 * pixels / window_size.1 as f32;
+            Matrix4::from_translation(ndc) * Matrix4::from_nonuniform_scale(xscale, yscale, 1.0) * layout
+        },
+
+        LabelSize::World(size) => {
+            let orientation = if options.billboard {
+                // the rows of the view rotation are the camera's right, up and backward axes
+                Matrix4::from_cols(
+                    Vector4::new(view.x.x, view.y.x, view.z.x, 0.0),
+                    Vector4::new(view.x.y, view.y.y, view.z.y, 0.0),
+                    Vector4::new(view.x.z, view.y.z, view.z.z, 0.0),
+                    Vector4::new(0.0, 0.0, 0.0, 1.0))
+            } else {
+                Matrix4::identity()
+            };
+            let model = Matrix4::from_translation(position.to_vec())
+                * orientation * Matrix4::from_scale(size) * layout;
+            view_projection * model
+        },
+    };
+
+    renderer.draw_text_transformed(text, &transform);
+}
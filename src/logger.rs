@@ -0,0 +1,109 @@
+// the `log` backend. every record goes to stderr, a log file that's rotated when it
+// gets too big, and the in-window console.
+use crate::console;
+use std::io::Write;
+use std::sync::Mutex;
+
+pub const LOG_PATH: &str = "glutin-skeleton.log";
+
+// rotate once the file reaches this size, keeping this many old files
+// (LOG_PATH.1 is the newest of them)
+const MAX_FILE_SIZE: u64 = 1024 * 1024;
+const ROTATED_FILES: usize = 3;
+
+struct LogFile {
+    file: std::fs::File,
+    size: u64,
+}
+
+struct Logger {
+    file: Mutex<Option<LogFile>>,
+}
+
+static LOGGER: Logger = Logger { file: Mutex::new(None) };
+
+// install the logger. can only be done once, so this belongs in main rather than main_1.
+pub fn init() -> Result<(), log::SetLoggerError> {
+    match open_log_file() {
+        Ok(file) => if let Ok(mut slot) = LOGGER.file.lock() {
+            *slot = Some(file);
+        },
+        Err(e) => eprintln!("couldn't open {}: {}", LOG_PATH, e),
+    }
+    log::set_logger(&LOGGER)?;
+    log::set_max_level(log::LevelFilter::Info);
+    Ok(())
+}
+
+// parse a level name as used by the log_level cvar, e.g. "debug" or "warn"
+pub fn parse_level(name: &str) -> Option<log::LevelFilter> {
+    name.parse().ok()
+}
+
+fn open_log_file() -> std::io::Result<LogFile> {
+    let file = std::fs::OpenOptions::new().create(true).append(true).open(LOG_PATH)?;
+    let size = file.metadata()?.len();
+    Ok(LogFile { file, size })
+}
+
+fn rotate() -> std::io::Result<LogFile> {
+    for index in (1..ROTATED_FILES).rev() {
+        let from = format!("{}.{}", LOG_PATH, index);
+        if std::path::Path::new(&from).exists() {
+            std::fs::rename(&from, format!("{}.{}", LOG_PATH, index + 1))?;
+        }
+    }
+    std::fs::rename(LOG_PATH, format!("{}.1", LOG_PATH))?;
+    open_log_file()
+}
+
+impl log::Log for Logger {
+    fn enabled(&self, metadata: &log::Metadata) -> bool {
+        metadata.level() <= log::max_level()
+    }
+
+    fn log(&self, record: &log::Record) {
+        if !self.enabled(record.metadata()) {
+            return;
+        }
+
+        let line = format!("{} {}: {}", record.level(), record.target(), record.args());
+        eprintln!("{}", line);
+
+        if let Ok(mut slot) = self.file.lock() {
+            if let Some(log_file) = slot.as_mut() {
+                let seconds = std::time::SystemTime::now().duration_since(std::time::UNIX_EPOCH)
+                    .map(|d| d.as_secs_f64()).unwrap_or(0.0);
+                let text = format!("{:.3} {}\n", seconds, line);
+                if log_file.file.write_all(text.as_bytes()).is_ok() {
+                    log_file.size += text.len() as u64;
+                }
+                if log_file.size >= MAX_FILE_SIZE {
+                    // the old file has to be closed before it can be renamed
+                    *slot = None;
+                    *slot = match rotate() {
+                        Ok(file) => Some(file),
+                        Err(e) => {
+                            eprintln!("couldn't rotate {}: {}", LOG_PATH, e);
+                            None
+                        },
+                    };
+                }
+            }
+        }
+
+        // the console only has room for the message itself
+        match record.level() {
+            log::Level::Error | log::Level::Warn => console::log(format!("{}: {}", record.level(), record.args())),
+            _ => console::log(record.args().to_string()),
+        }
+    }
+
+    fn flush(&self) {
+        if let Ok(mut slot) = self.file.lock() {
+            if let Some(log_file) = slot.as_mut() {
+                let _ = log_file.file.flush();
+            }
+        }
+    }
+}
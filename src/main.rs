@@ -5,7 +5,37 @@ use image;
 
 use gl_helpers::gl;
 
+mod camera;
+mod camera_path;
+mod console;
+mod controls;
+mod cvars;
+mod event_log;
+mod frame_stats;
+mod frame_uniforms;
+mod gl_context;
+mod gl_debug;
+mod gl_info;
+mod gl_object;
+mod glsl;
+mod glyph_atlas;
+mod gpu_timer;
+mod input_map;
+mod key_states;
+mod labels;
+mod logger;
+mod projection;
+mod screenshot;
+mod sdf_text;
+mod shape_renderer;
 mod simple_text;
+mod text_renderer;
+mod tracer;
+mod transform;
+mod truetype_text;
+mod ui;
+
+use text_renderer::TextRenderer;
 use std::collections::HashMap;
 
 macro_rules! cstr {
@@ -37,47 +67,152 @@ impl std::fmt::Display for Error {
 impl std::error::Error for Error {}
 
 fn main() -> Result<(), Box<dyn std::error::Error>> {
-    let mut parameters = HashMap::new();
+    if let Err(e) = logger::init() {
+        eprintln!("couldn't install the logger: {}", e);
+    }
+
+    let mut parameters = parameters_from_args(std::env::args().skip(1));
+    // --record-events=<file> saves the window events to a file, and --replay=<file>
+    // plays such a file back in a hidden window instead of handling real events.
+    // --expect-camera=x,y,z,yaw,pitch,roll makes a replay fail unless the camera
+    // ends there. a replay still needs a display, see event_log.
+    if let Some(path) = parameters.remove("record-events") {
+        event_log::start_recording(&path)?;
+    }
+    let expected_camera = match parameters.remove("expect-camera") {
+        Some(text) => Some(event_log::parse_camera(&text)?),
+        None => None,
+    };
+    if let Some(path) = parameters.remove("replay") {
+        event_log::start_replay(&path, expected_camera)?;
+    } else if expected_camera.is_some() {
+        log::warn!("ignoring --expect-camera without --replay");
+    }
+
     while let Some(restart_parameters) = main_1(parameters)? {
+        log::info!("restarting");
         parameters = restart_parameters;
     }
     Ok(())
 }
 
-fn main_1(parameters: HashMap<String, String>) -> Result<Option<HashMap<String, String>>, Box<dyn std::error::Error>> {
+// command line arguments are `--name=value` cvar overrides. a bare `--name` sets a
+// bool cvar to true.
+fn parameters_from_args<I: Iterator<Item = String>>(args: I) -> HashMap<String, String> {
+    let mut parameters = HashMap::new();
+    for arg in args {
+        let setting = match arg.strip_prefix("--") {
+            Some(setting) => setting,
+            None => {
+                log::warn!("ignoring argument `{}`, expected --name=value", arg);
+                continue;
+            },
+        };
+        let mut parts = setting.splitn(2, '=');
+        let name = parts.next().unwrap_or("").to_string();
+        let value = parts.next().unwrap_or("true").to_string();
+        parameters.insert(name, value);
+    }
+    parameters
+}
+
+fn main_1(mut parameters: HashMap<String, String>) -> Result<Option<HashMap<String, String>>, Box<dyn std::error::Error>> {
     let window_title = "nice window title";
     let mut window_size = (800, 600);
-    let gl_version = (4, 3);
-    let vsync = parameters.get("vsync")
-        .map_or(Ok(true), |v| v.parse::<bool>())?;
+
+    // --gl-info prints what the context supports as json, then exits
+    let print_gl_info = parameters.remove("gl-info").is_some();
+
+    // recordings and replays start from the defaults, not whatever's in the config
+    // files, so a recording replays the same anywhere. they don't save them either.
+    let replaying = event_log::is_replaying();
+    let use_config_files = !replaying && !event_log::is_recording();
+
+    let mut cvars = default_cvars();
+    if use_config_files && std::path::Path::new(CONFIG_PATH).exists() {
+        for problem in cvars.load(CONFIG_PATH)? {
+            log::warn!("{}: {}", CONFIG_PATH, problem);
+        }
+    }
+    for problem in cvars.apply_parameters(&parameters) {
+        log::warn!("{}", problem);
+    }
+
+    let mut input_map = input_map::InputMap::new();
+    if use_config_files && std::path::Path::new(INPUT_PATH).exists() {
+        for problem in input_map.load(INPUT_PATH)? {
+            log::warn!("{}: {}", INPUT_PATH, problem);
+        }
+    }
+
+    let vsync = cvars.bool("vsync");
+    let mut log_level = String::new();
+    apply_log_level(&cvars, &mut log_level);
 
     let mut ev_loop = glutin::EventsLoop::new();
-    let gl_window = {
+    let (gl_window, context_request) = {
         let window = glutin::WindowBuilder::new()
             .with_title(window_title)
-            .with_dimensions(window_size.into());
-        let gl_request = glutin::GlRequest::Specific(
-            glutin::Api::OpenGl, gl_version);
-        let context = glutin::ContextBuilder::new()
-            .with_gl_profile(glutin::GlProfile::Core)
-            .with_gl(gl_request)
-            .with_vsync(vsync);
-        glutin::GlWindow::new(window, context, &ev_loop)?
+            .with_dimensions(window_size.into())
+            .with_visibility(!print_gl_info && !replaying);
+        let (mut requests, problems) = gl_context::parse_requests(cvars.string("gl_contexts"));
+        for problem in problems {
+            log::warn!("gl_contexts: {}", problem);
+        }
+        if requests.is_empty() {
+            requests = gl_context::parse_requests(gl_context::DEFAULT_REQUESTS).0;
+        }
+        gl_context::create(&window, vsync, &requests, &ev_loop)?
     };
 
     unsafe {
         use glutin::GlContext;
         gl_window.make_current()?;
         gl::load_with(|sym| gl_window.get_proc_address(sym) as *const _);
-        gl::Enable(gl::DEBUG_OUTPUT);
-        gl::DebugMessageCallback(gl_debug_callback, std::ptr::null_mut());
         gl::Enable(gl::DEPTH_TEST);
     }
+    log::info!("created a {} context, vsync {}", context_request, vsync);
+
+    let gl_info = gl_info::GlInfo::probe();
+    if print_gl_info {
+        println!("{}", gl_info.to_json());
+        return Ok(None);
+    }
+    gl_info.log();
+
+    let gl_debug = if gl_info.supports_debug_output() {
+        let (settings, problems) = gl_debug::Settings::from_cvars(&cvars);
+        for problem in problems {
+            log::warn!("{}", problem);
+        }
+        Some(gl_debug::DebugOutput::install(settings))
+    } else {
+        log::warn!("gl debug output isn't supported, gl errors won't be reported");
+        None
+    };
+
+    // declared after the window so it runs just before the context is destroyed
+    let _leak_check = gl_object::LeakCheck;
 
     let mut quit = false;
-    let mut keystates = KeyStates::new();
-    let debug_printer = simple_text::SimpleTextContext::new();
 
+    let load_trace = tracer::scope("load resources");
+
+    let mut text_renderers: Vec<Box<dyn TextRenderer>> = vec![Box::new(simple_text::SimpleTextContext::new()?)];
+    {
+        let _trace = tracer::scope("load fonts");
+        let font_path = cvars.string("font");
+        match truetype_text::TrueTypeTextContext::from_file(font_path) {
+            Ok(context) => text_renderers.push(Box::new(context)),
+            Err(e) => log::warn!("truetype text unavailable, couldn't load {}: {}", font_path, e),
+        }
+        match sdf_text::SdfTextContext::from_file(font_path) {
+            Ok(context) => text_renderers.push(Box::new(context)),
+            Err(e) => log::warn!("sdf text unavailable, couldn't load {}: {}", font_path, e),
+        }
+    }
+
+    let shaders_trace = tracer::scope("load shaders");
     let multitext_data = {
         use std::io::BufRead;
         let file = std::fs::File::open("data/shader.multitext")?;
@@ -85,19 +220,31 @@ fn main_1(parameters: HashMap<String, String>) -> Result<Option<HashMap<String,
         multitext::parse_lines(file.lines().filter_map(|s| s.ok()))?
     };
 
-    let mut gl_data = GlData::default();
+    let program = {
+        // gles contexts use the `<name> es` sections where there are any
+        let section = |name: &str| {
+            let es_section = if context_request.is_es() { multitext_data.get(&format!("{} es", name)) } else { None };
+            es_section.or_else(|| multitext_data.get(name))
+                .ok_or_else(|| Error::new(format!("{} not found", name)))
+        };
+        let vcode = section("vertex shader")?;
+        let fcode = section("fragment shader")?;
 
-    gl_data.program = {
-        let vcode = multitext_data.get("vertex shader").ok_or_else(|| Error::new("vertex shader not found"))?;
-        let fcode = multitext_data.get("fragment shader").ok_or_else(|| Error::new("fragment shader not found"))?;
+        let (program, version) = glsl::build_program("scene", vcode, fcode)?;
+        log::info!("built the scene shaders from data/shader.multitext as {:?}", version);
+        program
+    };
+    drop(shaders_trace);
 
-        gl_helpers::ProgramBuilder::new()
-            .vertex_shader_code(vcode)
-            .fragment_shader_code(fcode)
-            .build()?
+    let mut gl_data = GlData {
+        program,
+        textures: Vec::new(),
+        buffers: Vec::new(),
+        vertex_arrays: Vec::new(),
     };
 
     {
+        let _trace = tracer::scope("load vertex arrays");
         let vertices: Vec<f32> = {
             multitext_data.get("vertices")
                 .ok_or_else(|| Error::new("vertices not found"))?
@@ -110,7 +257,7 @@ fn main_1(parameters: HashMap<String, String>) -> Result<Option<HashMap<String,
         };
 
         let buffer = gl_helpers::create_buffer(&vertices, gl_helpers::BufferUsage::StaticDraw)?;
-        gl_data.buffers.push(buffer);
+        let buffer = gl_object::Buffer::from_raw(buffer);
         
         let components = multitext_data.get("vertex components")
             .ok_or_else(|| Error::new("vertex components not found"))?
@@ -120,16 +267,33 @@ fn main_1(parameters: HashMap<String, String>) -> Result<Option<HashMap<String,
                 e => Some(e.parse()),
             })
             .collect::<Result<Vec<_>, _>>()?;
-        let vertex_array = gl_helpers::create_single_buffer_vertex_array(buffer, &components)?;
+        let vertex_array = gl_helpers::create_single_buffer_vertex_array(buffer.id(), &components)?;
+        let vertex_array = gl_object::VertexArray::from_raw(vertex_array);
+        gl_data.buffers.push(buffer);
         let vertex_count: i32 = vertices.len() as i32 / components.iter().sum::<i32>();
 
-        gl_data.vertex_arrays.push(VertexArray { 
-            id: vertex_array,
+        // the first component is the position
+        let stride = components.iter().sum::<i32>() as usize;
+        let mut bounds = (
+            cgmath::Point3::new(f32::MAX, f32::MAX, f32::MAX),
+            cgmath::Point3::new(f32::MIN, f32::MIN, f32::MIN));
+        for vertex in vertices.chunks(stride).filter(|v| v.len() >= 3) {
+            bounds.0 = cgmath::Point3::new(bounds.0.x.min(vertex[0]), bounds.0.y.min(vertex[1]), bounds.0.z.min(vertex[2]));
+            bounds.1 = cgmath::Point3::new(bounds.1.x.max(vertex[0]), bounds.1.y.max(vertex[1]), bounds.1.z.max(vertex[2]));
+        }
+
+        log::info!("loaded {} vertices with components {:?}", vertex_count, components);
+        gl_data.vertex_arrays.push(VertexArray {
+            vertex_array,
             vertex_count,
+            bounds,
+            transform: transform::Transform::default(),
+            spin_axis: Some(cgmath::Vector3::new(-1.0, -1.0, -1.0)),
         });
     }
 
     {
+        let _trace = tracer::scope("load textures");
         let paths_and_names = multitext_data.get("textures").cloned().unwrap_or_default()
             .split(",")
             .filter_map(|e| match e.trim() {
@@ -142,53 +306,129 @@ fn main_1(parameters: HashMap<String, String>) -> Result<Option<HashMap<String,
             }).collect::<Vec<_>>();
 
         for (path, name) in paths_and_names.into_iter() {
-            let uniform_location = gl_helpers::get_uniform_location(gl_data.program, name.as_ptr())?;
+            let uniform_location = gl_helpers::get_uniform_location(gl_data.program.id(), name.as_ptr())?;
 
-            let image_data = image::open(path)?.to_rgba();
+            let image_data = image::open(&path)?.to_rgba();
             let width = image_data.width() as i32;
             let height = image_data.height() as i32;
-            let mut id = 0;
+            let texture = gl_object::Texture::new();
             let image_ptr = image_data.as_ptr() as *const std::os::raw::c_void;
             unsafe {
-                gl::GenTextures(1, &mut id);
-                gl::BindTexture(gl::TEXTURE_2D, id);
+                gl::BindTexture(gl::TEXTURE_2D, texture.id());
                 gl::TexParameteri(gl::TEXTURE_2D, gl::TEXTURE_MIN_FILTER, gl::LINEAR as i32);
+                if let Some(max_anisotropy) = gl_info.max_anisotropy {
+                    let anisotropy = cvars.float("anisotropy").clamp(1.0, max_anisotropy);
+                    gl::TexParameterf(gl::TEXTURE_2D, gl_info::TEXTURE_MAX_ANISOTROPY, anisotropy);
+                }
                 gl::TexImage2D(gl::TEXTURE_2D, 0, gl::RGBA as i32, width, height,
                     0, gl::RGBA, gl::UNSIGNED_BYTE, image_ptr);
             }
 
-            gl_data.textures.push(Texture { width, height, id, uniform_location });
+            log::info!("loaded texture {} ({}x{})", path, width, height);
+            gl_data.textures.push(Texture { width, height, texture, uniform_location });
         }
 
         unsafe {
-            gl::UseProgram(gl_data.program);
+            gl::UseProgram(gl_data.program.id());
             for (index, tex) in gl_data.textures.iter().enumerate() {
                 gl::Uniform1i(tex.uniform_location, index as i32);
             }
         }
     }
 
-    let mvp_uniform_location = gl_helpers::get_uniform_location(gl_data.program, cstr!("mvp")).ok();
-    let time_uniform_location = gl_helpers::get_uniform_location(gl_data.program, cstr!("time")).ok();
-
-    let mut camera = Camera {
-        position: cgmath::Point3::new(0.0, 0.0, -5.0),
-        angle: cgmath::Deg(0.0),
+    drop(load_trace);
+
+    let scene_uniforms = SceneUniforms::find(gl_data.program.id());
+    let frame_uniforms = frame_uniforms::FrameUniforms::new();
+
+    let mut controls = controls::Controls::new();
+    let mut path_recording: Option<PathRecording> = None;
+    let mut playback: Option<Playback> = None;
+    let mut mouse_grabbed = false;
+
+    // gamepad events aren't in the event log, so recordings and replays go without
+    let mut gamepads = if replaying || event_log::is_recording() {
+        None
+    } else {
+        match gilrs::Gilrs::new() {
+            Ok(gamepads) => Some(gamepads),
+            Err(e) => {
+                log::warn!("no gamepad support: {}", e);
+                None
+            },
+        }
     };
 
     let mut time_of_last_update = std::time::Instant::now();
     let mut total_seconds_elapsed = 0.0;
+    // how far the last frame moved things on, for the frame uniforms
+    let mut last_frame_seconds = 0.0;
     let mut frame_counter = 0;
     let mut fps_frame_start = 0;
     let mut seconds_elapsed_since_last_fps_measurment = 0.0;
     let mut fps_text = "FPS: ???".to_owned();
 
+    let mut label_mode = 1;
+
+    let mut shapes = shape_renderer::ShapeRenderer::new()?;
+    let mut ui = ui::Ui::new();
+    let mut show_inspector = false;
+    let mut inspector_sections = InspectorSections::default();
+    let mut frame_stats = frame_stats::FrameStats::new();
+    let mut show_frame_stats = false;
+    let gpu_timer = gpu_timer::GpuTimer::new(gl_info.supports_timer_queries());
+    let clip_control = gl_info.supports_clip_control();
+
+    let mut console = console::Console::new();
+    register_console_commands(&mut console);
+    let mut console_target = ConsoleTarget {
+        cvars,
+        input_map,
+        reload: false,
+        camera_position: None,
+        screenshot: false,
+        gpu_csv: None,
+        path_command: None,
+        trace_frames: None,
+        quit: false,
+    };
+
     let mut restart = None;
 
     while !quit {
-        ev_loop.poll_events(|event|
-            if let glutin::Event::WindowEvent{event, ..} = event {
+        if tracer::frame(frame_counter) {
+            match tracer::save() {
+                Ok(path) => console.print(format!("saved {}", path)),
+                Err(e) => console.print(format!("couldn't save trace: {}", e)),
+            }
+        }
+        frame_stats.begin_phases();
+
+        let events_trace = tracer::scope("events");
+        console_target.input_map.new_frame();
+
+        let replay_frame = if replaying {
+            let frame = event_log::next_frame();
+            if frame.is_none() {
+                let c = &controls.camera;
+                event_log::finish_replay([c.position.x, c.position.y, c.position.z, c.yaw.0, c.pitch.0, c.roll.0])?;
+                quit = true;
+            }
+            frame
+        } else {
+            None
+        };
+
+        let window_id = gl_window.id();
+        let mut handle_event = |event: glutin::Event| match event {
+            glutin::Event::WindowEvent{event, ..} => {
                 use glutin::WindowEvent::*;
+                event_log::record(&event);
+                if show_inspector && ui.handle_event(&event) {
+                    return;
+                }
+                controls.handle_event(&event, &mut console_target.input_map, console.is_visible());
+
                 match event {
                     CloseRequested => quit = true,
 
@@ -203,208 +443,942 @@ fn main_1(parameters: HashMap<String, String>) -> Result<Option<HashMap<String,
                         }
                     },
 
-                    KeyboardInput{input, ..} => {
-                        if let Some(key) = input.virtual_keycode {
-                            keystates[key] = input.state == glutin::ElementState::Pressed;
+                    ReceivedCharacter(c) => console.receive_char(c, &mut console_target),
 
-                            if key == glutin::VirtualKeyCode::V && keystates[key] {
-                                // restart with vsync toggled, because we can't change vsync on an existing window
-                                let mut parameters = HashMap::new();
-                                parameters.insert("vsync".to_string(), format!("{}", !vsync));
-                                restart = Some(parameters);
-                                quit = true;
+                    Focused(false) => mouse_grabbed = set_mouse_grab(&gl_window, false),
+
+                    // everything else the keys do goes through the input map
+                    KeyboardInput{input: glutin::KeyboardInput{
+                        state: glutin::ElementState::Pressed, virtual_keycode: Some(key), ..}, ..} =>
+                    {
+                        if key == glutin::VirtualKeyCode::Grave {
+                            console.toggle();
+                            // anything held while the console opens or closes would otherwise stay held
+                            console_target.input_map.release_all();
+                            if mouse_grabbed {
+                                mouse_grabbed = set_mouse_grab(&gl_window, false);
                             }
+                        } else if key == glutin::VirtualKeyCode::Escape && console.is_visible() {
+                            console.toggle();
                         }
                     },
 
                     _ => (),
                 }
+            },
+
+            glutin::Event::DeviceEvent{event: glutin::DeviceEvent::MouseMotion{delta}, ..} => {
+                if mouse_grabbed {
+                    controls.add_mouse_motion(delta);
+                }
+            },
+
+            _ => (),
+        };
+
+        match replay_frame.as_ref() {
+            Some(frame) => {
+                for recorded in frame.events.iter() {
+                    handle_event(glutin::Event::WindowEvent { window_id, event: recorded.to_window_event() });
+                }
+                // the hidden window's own events are dropped
+                ev_loop.poll_events(|_| ());
+            },
+            None if replaying => ev_loop.poll_events(|_| ()),
+            None => ev_loop.poll_events(handle_event),
+        }
+        if let Some(gamepads) = gamepads.as_mut() {
+            while let Some(gilrs::Event { event, .. }) = gamepads.next_event() {
+                controls.handle_gamepad_event(&event, &mut console_target.input_map, console.is_visible());
+            }
+        }
+
+        // the input map only sees events while the console is closed, so these don't
+        // happen while typing
+        {
+            let input = &console_target.input_map;
+            if input.just_pressed("mouse_look") {
+                mouse_grabbed = set_mouse_grab(&gl_window, !mouse_grabbed);
+            }
+            if input.just_pressed("release_mouse") && mouse_grabbed {
+                mouse_grabbed = set_mouse_grab(&gl_window, false);
+            }
+
+            if input.just_pressed("next_text_renderer") {
+                let index = find_text_renderer(&text_renderers, &console_target.cvars);
+                let next = text_renderers[(index + 1) % text_renderers.len()].name();
+                let _ = console_target.cvars.set("text_renderer", next);
+            }
+
+            if input.just_pressed("next_projection") {
+                let modes = &projection::MODES;
+                let index = modes.iter().position(|&m| m == console_target.cvars.string("projection"));
+                let next = modes[index.map_or(0, |i| (i + 1) % modes.len())];
+                let _ = console_target.cvars.set("projection", next);
+                if next == "reversed_z" && !clip_control {
+                    console.print("reversed-z needs clip control, using an infinite far plane without it");
+                }
+            }
+
+            if input.just_pressed("next_label_mode") {
+                label_mode = (label_mode + 1) % LABEL_MODES.len();
+            }
+
+            controls.handle_actions(input, gl_data.bounds());
+
+            if input.just_pressed("toggle_inspector") {
+                show_inspector = !show_inspector;
+            }
+
+            if input.just_pressed("toggle_frame_stats") {
+                show_frame_stats = !show_frame_stats;
+            }
+
+            for (action, name, step) in CVAR_ACTIONS.iter() {
+                if input.just_pressed(action) {
+                    if let Err(e) = console_target.cvars.adjust(name, *step) {
+                        console.print(e);
+                    }
+                }
+            }
+
+            if input.just_pressed("screenshot") {
+                console_target.screenshot = true;
+            }
+            if input.just_pressed("quit") {
+                quit = true;
+            }
+        }
+
+        let (screenshot_requested, gpu_csv_path, path_command) = {
+            let target = &mut console_target;
+
+            // vsync can't be changed on an existing window, so restart to apply it
+            if target.cvars.bool("vsync") != vsync || target.reload {
+                if target.reload {
+                    log::info!("reloading all resources");
+                } else {
+                    log::info!("vsync changed, restarting to apply it");
+                }
+                restart = Some(target.cvars.to_parameters());
+                quit = true;
             }
-        );
 
+            if let Some(position) = target.camera_position.take() {
+                controls.place(camera::Camera { position, ..controls.camera });
+            }
+
+            if let Some(frames) = target.trace_frames.take() {
+                tracer::start(frames);
+            }
+
+            quit = quit || target.quit;
+            target.reload = false;
+            (std::mem::replace(&mut target.screenshot, false), target.gpu_csv.take(), target.path_command.take())
+        };
+
+        match path_command {
+            Some(PathCommand::Record(file)) => {
+                console.print(format!("recording the camera to {}", file));
+                path_recording = Some(PathRecording { file, path: camera_path::CameraPath::new(), time: 0.0 });
+            },
+            Some(PathCommand::StopRecording) => match path_recording.take() {
+                Some(recording) => match recording.save() {
+                    Ok(message) | Err(message) => console.print(message),
+                },
+                None => console.print("not recording"),
+            },
+            Some(PathCommand::Play(file)) => match camera_path::CameraPath::load(&file) {
+                Ok(path) => {
+                    // start the scene's animation over too, so every playback renders the same frames
+                    controls.place(path.sample(0.0).unwrap_or(controls.camera));
+                    total_seconds_elapsed = 0.0;
+                    console.print(format!("playing {} ({:.1} seconds)", file, path.duration()));
+                    playback = Some(Playback { path, time: 0.0, frames: 0, started: std::time::Instant::now() });
+                },
+                Err(e) => console.print(format!("couldn't load {}: {}", file, e)),
+            },
+            Some(PathCommand::Stop) => playback = None,
+            None => (),
+        }
+
+        if let Some(path) = gpu_csv_path {
+            match gpu_timer.write_csv(&path) {
+                Ok(rows) => console.print(format!("wrote {} rows to {}", rows, path)),
+                Err(e) => console.print(format!("couldn't write {}: {}", path, e)),
+            }
+        }
+
+        let cvars = &console_target.cvars;
+        apply_log_level(cvars, &mut log_level);
+        let text_renderer_index = find_text_renderer(&text_renderers, cvars);
+        {
+            let renderer = &mut text_renderers[text_renderer_index];
+            let weight = cvars.float("text_weight");
+            if renderer.weight() != weight {
+                renderer.set_weight(weight);
+            }
+        }
+        if let Some(gl_debug) = &gl_debug {
+            let (settings, problems) = gl_debug::Settings::from_cvars(cvars);
+            if gl_debug.settings().as_ref() != Some(&settings) {
+                for problem in problems {
+                    console.print(problem);
+                }
+                gl_debug.set_settings(settings);
+            }
+        }
+        console.drain_log();
+        drop(events_trace);
+        frame_stats.end_phase("events");
+
+        let projection_mode = projection::Projection::from_cvars(cvars, clip_control);
+        let projection = projection_mode.matrix(projection::aspect_ratio(window_size));
+        let view = controls.camera.view();
+
+        let uniforms_trace = tracer::scope("uniforms");
+        projection_mode.begin_depth();
         unsafe {
             gl::ClearColor(0.0, 0.0, 0.0, 1.0);
             gl::Clear(gl::COLOR_BUFFER_BIT|gl::DEPTH_BUFFER_BIT);
 
-            gl::UseProgram(gl_data.program);
-            if let Some(loc) = time_uniform_location {
+            gl::UseProgram(gl_data.program.id());
+            if let Some(loc) = scene_uniforms.time {
                 gl::Uniform1f(loc, total_seconds_elapsed as f32);
             }
-
-            if let Some(loc) = mvp_uniform_location {
-                let aspect = (window_size.0 as f32) / (window_size.1 as f32);
-                let projection = cgmath::perspective(cgmath::Deg(85.0), aspect, 0.2, 1000.0);
-                let move_vector = vector_zx_from_angle(camera.angle);
-                let up_vector = cgmath::Vector3::new(0.0, 1.0, 0.0);
-                let view = cgmath::Matrix4::look_at_dir(camera.position, move_vector, up_vector);
-
-                let mvp = projection * view;
-                use cgmath::Matrix;
-                gl::UniformMatrix4fv(loc, 1, gl::FALSE, mvp.as_ptr());
+        }
+        scene_uniforms.set_matrix4(scene_uniforms.view, &view);
+        scene_uniforms.set_matrix4(scene_uniforms.projection, &projection);
+        frame_uniforms.update(&frame_uniforms::FrameData {
+            view: view.into(),
+            projection: projection.into(),
+            view_projection: (projection * view).into(),
+            camera_position: controls.camera.position.into(),
+            time: total_seconds_elapsed as f32,
+            resolution: [window_size.0 as f32, window_size.1 as f32],
+            delta_time: last_frame_seconds as f32,
+            frame_index: frame_counter as u32,
+        });
+        for v in gl_data.vertex_arrays.iter_mut() {
+            if let Some(axis) = v.spin_axis {
+                use cgmath::{InnerSpace, Rotation3};
+                v.transform.rotation = cgmath::Quaternion::from_axis_angle(axis.normalize(), cgmath::Rad(total_seconds_elapsed as f32));
             }
+        }
+        let model_matrices = gl_data.model_matrices();
+        drop(uniforms_trace);
+        frame_stats.end_phase("uniforms");
 
-            for (index, tex) in gl_data.textures.iter().enumerate() {
-                gl::ActiveTexture(gl::TEXTURE0 + index as u32);
-                gl::BindTexture(gl::TEXTURE_2D, tex.id);
+        {
+            let _scope = gpu_timer.scope("scene");
+            let _trace = tracer::scope("scene");
+            unsafe {
+                for (index, tex) in gl_data.textures.iter().enumerate() {
+                    gl::ActiveTexture(gl::TEXTURE0 + index as u32);
+                    gl::BindTexture(gl::TEXTURE_2D, tex.texture.id());
+                }
+
+                for (index, v) in gl_data.vertex_arrays.iter().enumerate() {
+                    let _trace = tracer::scope("draw vertex array").arg("index", index as i64);
+                    scene_uniforms.set_model(&model_matrices[index], &view, &projection);
+                    gl::BindVertexArray(v.vertex_array.id());
+                    gl::DrawArrays(gl::TRIANGLES, 0, v.vertex_count);
+                }
             }
+        }
 
-            for v in gl_data.vertex_arrays.iter() {
-                gl::BindVertexArray(v.id);
-                gl::DrawArrays(gl::TRIANGLES, 0, v.vertex_count);
+        let text_scope = gpu_timer.scope("text");
+        let text_trace = tracer::scope("text");
+
+        if let Some(options) = LABEL_MODES[label_mode] {
+            let anchors = [
+                ("cube", cgmath::Point3::new(0.0, 1.0, 0.0)),
+                ("x", cgmath::Point3::new(1.5, 0.0, 0.0)),
+                ("y", cgmath::Point3::new(0.0, 1.5, 0.0)),
+                ("z", cgmath::Point3::new(0.0, 0.0, 1.5)),
+            ];
+            for (text, position) in anchors.iter() {
+                labels::draw_label(text_renderers[text_renderer_index].as_ref(), text, *position,
+                    &view, &projection, window_size, &options);
             }
         }
+        projection_mode.end_depth();
 
         {
             let x = -1.0 + 40.0 / window_size.0 as f32;
             let y = -1.0 + 40.0 / window_size.1 as f32;
             let xscale = 25.0 / window_size.0 as f32;
             let yscale = 25.0 / window_size.1 as f32;
-            debug_printer.draw_text(&fps_text, x, y, xscale, yscale);
+            text_renderers[text_renderer_index].draw_text(&fps_text, x, y, xscale, yscale);
+        }
+        drop(text_trace);
+        drop(text_scope);
+
+        let overlay_scope = gpu_timer.scope("overlay");
+        let overlay_trace = tracer::scope("overlay");
+        if show_frame_stats {
+            draw_frame_stats(&frame_stats, &gpu_timer, &mut shapes, text_renderers[text_renderer_index].as_ref(), window_size);
+        }
+
+        let inspector_changes = if show_inspector {
+            let inspected = Inspected {
+                gl_data: &gl_data,
+                camera: &controls.camera,
+                orbit: controls.orbit.as_ref(),
+                cvars,
+                uniforms: &scene_uniforms,
+                total_seconds_elapsed,
+                frame_stats: &frame_stats,
+            };
+            ui.begin(window_size.0 as f32 - 270.0, 10.0, 260.0);
+            let changes = draw_inspector(&mut ui, &mut inspector_sections, &inspected);
+            ui.end(&mut shapes, text_renderers[text_renderer_index].as_ref(), window_size);
+            changes
+        } else {
+            InspectorChanges::default()
+        };
+
+        console.draw(text_renderers[text_renderer_index].as_ref(), window_size);
+        drop(overlay_trace);
+        drop(overlay_scope);
+        gpu_timer.end_frame();
+
+        if screenshot_requested {
+            match screenshot::save(window_size) {
+                Ok(path) => console.print(format!("saved {}", path)),
+                Err(e) => console.print(format!("screenshot failed: {}", e)),
+            }
         }
 
+        frame_stats.end_phase("draw");
+
+        let swap_trace = tracer::scope("swap_buffers");
         gl_window.swap_buffers()?;
+        drop(swap_trace);
+        frame_stats.end_phase("swap");
 
-        let seconds_elapsed_this_frame = {
+        let wall_seconds_this_frame = {
             let now = std::time::Instant::now();
             let time_elapsed_this_frame = now - time_of_last_update;
             time_of_last_update = now;
             time_elapsed_this_frame.as_nanos() as f64 / 1000000000.0
         };
+        frame_stats.push_frame_time(wall_seconds_this_frame);
 
-        {
-            use glutin::VirtualKeyCode as Vk;
-            let speed = 3.0;
-            let turnspeed = cgmath::Deg(120.0);
+        // a replay steps by the recorded frame times, so things move just as they did
+        let seconds_elapsed_this_frame = replay_frame.as_ref().map_or(wall_seconds_this_frame, |frame| frame.seconds);
+        event_log::end_frame(seconds_elapsed_this_frame);
 
-            let move_vector = vector_zx_from_angle(camera.angle);
-            let seconds_elapsed = seconds_elapsed_this_frame as f32;
+        // playback steps by the same amount every frame, whatever the real frame time
+        let simulated_seconds = match playback {
+            Some(_) => cvars.float("playback_frame_time") as f64,
+            None => seconds_elapsed_this_frame,
+        };
 
-            if keystates[Vk::W] {
-                camera.position += move_vector * speed * seconds_elapsed;
-            } else if keystates[Vk::S] {
-                camera.position -= move_vector * speed * seconds_elapsed;
+        let playback_finished = match playback.as_mut() {
+            Some(playback) => {
+                playback.time += simulated_seconds as f32;
+                playback.frames += 1;
+                if let Some(sampled) = playback.path.sample(playback.time) {
+                    controls.camera = sampled;
+                }
+                playback.time > playback.path.duration()
+            },
+            None => false,
+        };
+        if playback_finished {
+            if let Some(playback) = playback.take() {
+                let seconds = playback.started.elapsed().as_nanos() as f64 / 1000000000.0;
+                let line = format!("played {} frames in {:.2} seconds, {:.1} fps",
+                    playback.frames, seconds, playback.frames as f64 / seconds.max(0.000001));
+                log::info!("{}", line);
             }
+        }
 
-            if keystates[Vk::Q] {
-                let right = vector_zx_from_angle(camera.angle + cgmath::Deg(90.0));
-                camera.position += right * speed * seconds_elapsed;
-            } else if keystates[Vk::E] {
-                let left = vector_zx_from_angle(camera.angle - cgmath::Deg(90.0));
-                camera.position += left * speed * seconds_elapsed;
-            }
+        // orthographic zoom changes the view's height rather than the orbit distance
+        let mut ortho_height = None;
+        if playback.is_none() {
+            let _trace = tracer::scope("update");
+            ortho_height = controls.update(&console_target.input_map, cvars, &projection_mode, window_size.1,
+                gl_data.bounds(), seconds_elapsed_this_frame as f32);
+        }
 
-            if keystates[Vk::A] {
-                camera.angle += turnspeed * seconds_elapsed;
-            } else if keystates[Vk::D] {
-                camera.angle -= turnspeed * seconds_elapsed;
+        if let Some(height) = ortho_height {
+            // past the cvar's range the zoom just stops
+            let _ = console_target.cvars.set("ortho_height", &height.to_string());
+        }
+        for (name, value) in inspector_changes.cvars {
+            if let Err(e) = console_target.cvars.set(name, &value) {
+                console.print(e);
             }
         }
+        if inspector_changes.reset_camera {
+            controls.place(camera::Camera::default());
+        }
 
         frame_counter += 1;
-        total_seconds_elapsed += seconds_elapsed_this_frame;
+        total_seconds_elapsed += simulated_seconds;
+        last_frame_seconds = simulated_seconds;
+
+        if let Some(recording) = path_recording.as_mut() {
+            recording.time += simulated_seconds as f32;
+            recording.path.push(recording.time, controls.camera);
+        }
         seconds_elapsed_since_last_fps_measurment += seconds_elapsed_this_frame;
 
         if seconds_elapsed_since_last_fps_measurment >= 1.0 {
             let frames_for_this_measurement = frame_counter - fps_frame_start;
             let fps = (frames_for_this_measurement as f64) / seconds_elapsed_since_last_fps_measurment;
-            fps_text = format!("FPS: {}", fps);
+            fps_text = format!("FPS: {:.1}", fps);
+            if let Some(gl_debug) = &gl_debug {
+                gl_debug.report_repeats();
+            }
             fps_frame_start = frame_counter;
             seconds_elapsed_since_last_fps_measurment = 0.0;
         }
     }
 
+    if use_config_files {
+        if let Err(e) = console_target.cvars.save(CONFIG_PATH) {
+            log::warn!("couldn't save {}: {}", CONFIG_PATH, e);
+        }
+        if let Err(e) = console_target.input_map.save(INPUT_PATH) {
+            log::warn!("couldn't save {}: {}", INPUT_PATH, e);
+        }
+    }
+
+    // a camera path doesn't carry on across a restart like a trace does, so save
+    // what was recorded rather than losing it
+    if let Some(recording) = path_recording.take() {
+        match recording.save() {
+            Ok(message) => log::info!("{}", message),
+            Err(message) => log::warn!("{}", message),
+        }
+    }
+
+    // a trace keeps recording across a restart, but should be saved before exiting
+    if restart.is_none() && tracer::is_recording() {
+        match tracer::save() {
+            Ok(path) => log::info!("saved {}", path),
+            Err(e) => log::warn!("couldn't save trace: {}", e),
+        }
+    }
+
     Ok(restart)
 }
 
-extern "system" fn gl_debug_callback(
-    source: u32, ty: u32, id: u32, severity: u32, length: i32,
-    message: *const std::os::raw::c_char, user_param: *mut std::os::raw::c_void)
-{
-    let _ = (source, ty, id, severity, user_param);
+const CONFIG_PATH: &str = "config.cfg";
+const INPUT_PATH: &str = "input.cfg";
+const GPU_CSV_PATH: &str = "gpu_times.csv";
+const TRACE_FRAMES: u64 = 120;
+
+fn default_cvars() -> cvars::Cvars {
+    use cvars::CvarValue::*;
+    let mut cvars = cvars::Cvars::new();
+    cvars.register("vsync", Bool(true), "wait for vertical sync, takes effect by restarting");
+    cvars.register("font", Str("data/DejaVuSansMono.ttf".to_string()), "font file for the truetype and sdf text, read at startup");
+    cvars.register("text_renderer", Str("stroke".to_string()), "stroke, truetype or sdf");
+    cvars.register("text_weight", Float(0.0), "extra text line thickness, in text units");
+    cvars.register("camera_speed", Float(3.0), "top camera movement speed, in units per second");
+    cvars.register("camera_acceleration", Float(30.0), "how quickly the camera reaches its speed, in units per second per second");
+    cvars.register("camera_damping", Float(6.0), "how quickly the camera stops once no movement key is held");
+    cvars.set_range("camera_speed", 0.1, 1000.0);
+    cvars.set_range("camera_damping", 0.0, 100.0);
+    cvars.register("camera_sprint_multiplier", Float(3.0), "camera speed multiplier while shift is held");
+    cvars.register("camera_slow_multiplier", Float(0.25), "camera speed multiplier while ctrl is held");
+    cvars.register("camera_turn_speed", Float(120.0), "camera turn speed, in degrees per second");
+    cvars.register("mouse_sensitivity", Float(0.15), "mouse look speed, in degrees per count of mouse motion");
+    cvars.register("invert_mouse_y", Bool(false), "look up when the mouse moves down");
+    cvars.register("orbit_sensitivity", Float(0.3), "orbit camera rotation, in degrees per pixel dragged");
+    cvars.register("playback_frame_time", Float(1.0 / 60.0), "seconds each frame advances camera path playback by");
+    cvars.set_range("playback_frame_time", 0.001, 1.0);
+    cvars.register("fov", Float(85.0), "vertical field of view, in degrees");
+    cvars.register("near_plane", Float(0.2), "distance to the near clip plane");
+    cvars.register("far_plane", Float(1000.0), "distance to the far clip plane");
+    // cgmath::perspective panics outside these. the near plane's range ends
+    // below the far plane's, so the far plane is always further away.
+    cvars.set_range("fov", 1.0, 179.0);
+    cvars.set_range("near_plane", 0.001, 5.0);
+    cvars.set_range("far_plane", 10.0, 1000000.0);
+    cvars.register("projection", Str("perspective".to_string()), "perspective, reversed_z (infinite far plane) or orthographic");
+    cvars.register("ortho_height", Float(10.0), "height of the orthographic view, in world units");
+    cvars.set_range("ortho_height", 0.01, 1000000.0);
+    cvars.register("gl_contexts", Str(gl_context::DEFAULT_REQUESTS.to_string()), "gl contexts to try creating, best first, e.g. gl4.3, gl3.3 compat or es3.0");
+    cvars.register("anisotropy", Float(8.0), "anisotropic filtering for scene textures, where supported, read at startup");
+    cvars.register("log_level", Str("info".to_string()), "least severe log messages shown: off, error, warn, info, debug or trace");
+    cvars.register("gl_debug_severity", Str("low".to_string()), "least severe gl debug message shown: notification, low, medium or high");
+    cvars.register("gl_debug_ignore_ids", Str(String::new()), "comma separated gl debug message ids to hide");
+    cvars.register("gl_debug_break", Bool(false), "abort with a backtrace on high severity gl errors, in debug builds");
+    cvars
+}
 
-    if severity != gl::DEBUG_SEVERITY_NOTIFICATION {
-        unsafe {
-            let message = std::slice::from_raw_parts(message as *const u8, length as usize);
-            let message = std::str::from_utf8(message).expect("bad opengl error message");
-            println!("{}", message);
+// actions that adjust a cvar each time they happen. bool cvars are flipped.
+const CVAR_ACTIONS: [(&str, &str, f32);7] = [
+    ("toggle_vsync", "vsync", 0.0),
+    ("thinner_text", "text_weight", -0.02),
+    ("bolder_text", "text_weight", 0.02),
+    ("slower_camera", "camera_speed", -0.5),
+    ("faster_camera", "camera_speed", 0.5),
+    ("narrower_fov", "fov", -5.0),
+    ("wider_fov", "fov", 5.0),
+];
+
+// set the log level from the log_level cvar, if it changed since `applied`
+fn apply_log_level(cvars: &cvars::Cvars, applied: &mut String) {
+    let name = cvars.string("log_level");
+    if name == applied {
+        return;
+    }
+    *applied = name.to_string();
+    match logger::parse_level(name) {
+        Some(level) => log::set_max_level(level),
+        None => log::warn!("unknown log level `{}`, expected off, error, warn, info, debug or trace", name),
+    }
+}
+
+fn find_text_renderer(text_renderers: &[Box<dyn TextRenderer>], cvars: &cvars::Cvars) -> usize {
+    let name = cvars.string("text_renderer");
+    text_renderers.iter().position(|r| r.name() == name).unwrap_or(0)
+}
+
+// state console commands can reach. cvars are changed in place, the rest are
+// requests that main_1 acts on after the events are processed.
+struct ConsoleTarget {
+    cvars: cvars::Cvars,
+    input_map: input_map::InputMap,
+    reload: bool,
+    camera_position: Option<cgmath::Point3<f32>>,
+    screenshot: bool,
+    gpu_csv: Option<String>,
+    path_command: Option<PathCommand>,
+    trace_frames: Option<u64>,
+    quit: bool,
+}
+
+enum PathCommand {
+    Record(String),
+    StopRecording,
+    Play(String),
+    Stop,
+}
+
+// a camera path being recorded, a pose per frame
+struct PathRecording {
+    file: String,
+    path: camera_path::CameraPath,
+    time: f32,
+}
+
+impl PathRecording {
+    // what happened, for the console or the log
+    fn save(&self) -> Result<String, String> {
+        match self.path.save(&self.file) {
+            Ok(()) => Ok(format!("saved {} poses to {}", self.path.len(), self.file)),
+            Err(e) => Err(format!("couldn't save {}: {}", self.file, e)),
         }
     }
 }
 
-fn vector_zx_from_angle<A: cgmath::Angle<Unitless = f32>>(angle: A) -> cgmath::Vector3<f32> {
-    cgmath::Vector3::new(angle.sin(), 0.0, angle.cos())
+struct Playback {
+    path: camera_path::CameraPath,
+    time: f32,
+    frames: u32,
+    started: std::time::Instant,
 }
 
-#[derive(Debug, Clone, Copy)]
+fn register_console_commands(console: &mut console::Console<ConsoleTarget>) {
+    console.register("set", "set <cvar> <value>", |target, args| match args {
+        [name, value] => target.cvars.set(name, value).map(|_| String::new()),
+        _ => Err("expected a name and a value".to_string()),
+    });
+
+    console.register("get", "get <cvar>", |target, args| match args {
+        [name] => target.cvars.get(name)
+            .map(|value| format!("{} = {}", name, value))
+            .ok_or_else(|| format!("no cvar named `{}`", name)),
+        _ => Err("expected a name".to_string()),
+    });
+
+    console.register("reset", "reset <cvar> - set a cvar back to its default", |target, args| match args {
+        [name] => target.cvars.reset(name).map(|_| String::new()),
+        _ => Err("expected a name".to_string()),
+    });
+
+    console.register("cvars", "cvars - list every cvar", |target, _| {
+        Ok(target.cvars.describe().join("\n"))
+    });
+
+    console.register("save", "save - write the cvars and input bindings to their config files", |target, _| {
+        target.cvars.save(CONFIG_PATH).map_err(|e| e.to_string())?;
+        target.input_map.save(INPUT_PATH).map_err(|e| e.to_string())?;
+        Ok(format!("saved {} and {}", CONFIG_PATH, INPUT_PATH))
+    });
+
+    console.register("bind", "bind <action> [inputs] - show or set what an action is bound to, e.g. bind sprint LShift, mouse:4, gamepad:leftthumb", |target, args| match args.split_first() {
+        None => Err("expected an action".to_string()),
+        Some((action, [])) => target.input_map.binding(action)
+            .map(|inputs| format!("{} = {}", action, inputs))
+            .ok_or_else(|| format!("no action named `{}`", action)),
+        Some((action, inputs)) => {
+            let inputs = input_map::parse_inputs(&inputs.join(" "))?;
+            target.input_map.bind(action, inputs).map(|_| String::new())
+        },
+    });
+
+    console.register("unbind", "unbind <action> - bind an action to nothing", |target, args| match args {
+        [action] => target.input_map.bind(action, Vec::new()).map(|_| String::new()),
+        _ => Err("expected an action".to_string()),
+    });
+
+    console.register("bindings", "bindings - list every action and what it's bound to", |target, _| {
+        Ok(target.input_map.describe().join("\n"))
+    });
+
+    console.register("reload", "reload - restart, reloading all resources", |target, _| {
+        target.reload = true;
+        Ok(String::new())
+    });
+
+    console.register("camera", "camera <x> <y> <z>", |target, args| {
+        let coords = args.iter().map(|a| a.parse::<f32>()).collect::<Result<Vec<_>, _>>()
+            .map_err(|e| e.to_string())?;
+        match coords[..] {
+            [x, y, z] => {
+                target.camera_position = Some(cgmath::Point3::new(x, y, z));
+                Ok(String::new())
+            },
+            _ => Err("expected 3 coordinates".to_string()),
+        }
+    });
+
+    console.register("screenshot", "screenshot - save the next frame as a png", |target, _| {
+        target.screenshot = true;
+        Ok(String::new())
+    });
+
+    console.register("gpu_csv", "gpu_csv [path] - save recent gpu pass timings", |target, args| match args {
+        [] => {
+            target.gpu_csv = Some(GPU_CSV_PATH.to_string());
+            Ok(String::new())
+        },
+        [path] => {
+            target.gpu_csv = Some(path.to_string());
+            Ok(String::new())
+        },
+        _ => Err("expected at most one path".to_string()),
+    });
+
+    console.register("trace", "trace [frames] - record a chrome trace of the next frames, reload to include loading", |target, args| {
+        let frames = match args {
+            [] => TRACE_FRAMES,
+            [frames] => frames.parse().map_err(|e: std::num::ParseIntError| e.to_string())?,
+            _ => return Err("expected at most a frame count".to_string()),
+        };
+        target.trace_frames = Some(frames);
+        Ok(format!("tracing {} frames", frames))
+    });
+
+    console.register("record_path", "record_path [file] - record the camera to a file, or stop recording", |target, args| match args {
+        [] => {
+            target.path_command = Some(PathCommand::StopRecording);
+            Ok(String::new())
+        },
+        [file] => {
+            target.path_command = Some(PathCommand::Record(file.to_string()));
+            Ok(String::new())
+        },
+        _ => Err("expected at most a file name".to_string()),
+    });
+
+    console.register("play_path", "play_path <file> - move the camera along a recorded or authored path", |target, args| match args {
+        [file] => {
+            target.path_command = Some(PathCommand::Play(file.to_string()));
+            Ok(String::new())
+        },
+        _ => Err("expected a file name".to_string()),
+    });
+
+    console.register("stop_path", "stop_path - stop playing a camera path", |target, _| {
+        target.path_command = Some(PathCommand::Stop);
+        Ok(String::new())
+    });
+
+    console.register("quit", "quit", |target, _| {
+        target.quit = true;
+        Ok(String::new())
+    });
+}
+
+// which parts of the scene inspector are expanded
+struct InspectorSections {
+    vertex_arrays: bool,
+    textures: bool,
+    uniforms: bool,
+    camera: bool,
+    fps: bool,
+}
+
+impl Default for InspectorSections {
+    fn default() -> InspectorSections {
+        InspectorSections {
+            vertex_arrays: true,
+            textures: true,
+            uniforms: false,
+            camera: true,
+            fps: true,
+        }
+    }
+}
+
+// everything the scene inspector shows
+struct Inspected<'a> {
+    gl_data: &'a GlData,
+    camera: &'a camera::Camera,
+    orbit: Option<&'a camera::OrbitCamera>,
+    cvars: &'a cvars::Cvars,
+    uniforms: &'a SceneUniforms,
+    total_seconds_elapsed: f64,
+    frame_stats: &'a frame_stats::FrameStats,
+}
+
+// changes made through the inspector, applied once the frame is done with what they change
+#[derive(Default)]
+struct InspectorChanges {
+    cvars: Vec<(&'static str, String)>,
+    reset_camera: bool,
+}
+
+fn draw_inspector(ui: &mut ui::Ui, sections: &mut InspectorSections, scene: &Inspected) -> InspectorChanges {
+    let mut changes = InspectorChanges::default();
+
+    ui.header("vertex arrays", &mut sections.vertex_arrays);
+    if sections.vertex_arrays {
+        for v in scene.gl_data.vertex_arrays.iter() {
+            ui.label(&format!("vao {}: {} vertices", v.vertex_array.id(), v.vertex_count));
+        }
+    }
+
+    ui.header("textures", &mut sections.textures);
+    if sections.textures {
+        for (index, t) in scene.gl_data.textures.iter().enumerate() {
+            ui.label(&format!("texture {}: {}x{}, unit {}", t.texture.id(), t.width, t.height, index));
+        }
+    }
+
+    ui.header("uniforms", &mut sections.uniforms);
+    if sections.uniforms {
+        let location = |l: Option<i32>| l.map_or("unused".to_string(), |l| format!("location {}", l));
+        ui.label(&format!("program {}", scene.gl_data.program.id()));
+        let u = scene.uniforms;
+        for &(name, l) in [("model", u.model), ("view", u.view), ("projection", u.projection),
+            ("normal_matrix", u.normal_matrix), ("mvp", u.mvp)].iter()
+        {
+            ui.label(&format!("{}: {}", name, location(l)));
+        }
+        ui.label(&format!("time: {}", location(u.time)));
+        ui.label(&format!("{} block: binding {}, time {:.2}",
+            frame_uniforms::BLOCK_NAME, frame_uniforms::BINDING, scene.total_seconds_elapsed));
+        for t in scene.gl_data.textures.iter() {
+            ui.label(&format!("sampler: location {}", t.uniform_location));
+        }
+    }
+
+    ui.header("camera", &mut sections.camera);
+    if sections.camera {
+        let p = scene.camera.position;
+        ui.label(&format!("position: {:.2} {:.2} {:.2}", p.x, p.y, p.z));
+        ui.label(&format!("yaw {:.1}, pitch {:.1}, roll {:.1}",
+            scene.camera.yaw.0, scene.camera.pitch.0, scene.camera.roll.0));
+        match scene.orbit {
+            Some(orbit) => ui.label(&format!("orbiting {:.2} {:.2} {:.2} at {:.2}",
+                orbit.target.x, orbit.target.y, orbit.target.z, orbit.distance)),
+            None => ui.label("flying"),
+        }
+
+        for &(name, min, max) in [("fov", 30.0, 120.0), ("camera_speed", 0.5, 20.0)].iter() {
+            let mut value = scene.cvars.float(name);
+            if ui.slider(name, &mut value, min, max) {
+                changes.cvars.push((name, value.to_string()));
+            }
+        }
+
+        let mut vsync = scene.cvars.bool("vsync");
+        if ui.checkbox("vsync", &mut vsync) {
+            changes.cvars.push(("vsync", vsync.to_string()));
+        }
+
+        changes.reset_camera = ui.button("reset camera");
+    }
+
+    ui.header("frame times", &mut sections.fps);
+    if sections.fps {
+        let summary = scene.frame_stats.summary();
+        ui.label(&format!("max {:.2} ms", summary.max * 1000.0));
+        let values: Vec<f32> = scene.frame_stats.frame_times().iter().map(|t| *t as f32).collect();
+        ui.graph(&values, summary.max as f32, 60.0);
+    }
+
+    changes
+}
+
+// frame time summary and per-phase timings above the fps counter, with a graph above those
+fn draw_frame_stats(stats: &frame_stats::FrameStats, gpu_timer: &gpu_timer::GpuTimer, shapes: &mut shape_renderer::ShapeRenderer,
+    text: &dyn TextRenderer, window_size: (u32, u32))
+{
+    let (w, h) = (window_size.0 as f32, window_size.1 as f32);
+    if w == 0.0 || h == 0.0 {
+        return;
+    }
+
+    let summary = stats.summary();
+    let phases = stats.phases().iter()
+        .map(|(name, seconds)| format!("{} {:.2}", name, seconds * 1000.0))
+        .collect::<Vec<_>>()
+        .join("  ");
+    let gpu_text = if gpu_timer.is_supported() {
+        let passes = gpu_timer.passes().iter()
+            .map(|(name, seconds)| format!("{} {:.2}", name, seconds * 1000.0))
+            .collect::<Vec<_>>()
+            .join("  ");
+        format!("gpu ms: {}  ({} dropped)", passes, gpu_timer.dropped_frames())
+    } else {
+        "gpu ms: timer queries not supported".to_string()
+    };
+    let lines = [
+        format!("frame ms min {:.2} avg {:.2} max {:.2}", summary.min * 1000.0, summary.average * 1000.0, summary.max * 1000.0),
+        format!("avg {:.1} fps, 1% low {:.1}, 0.1% low {:.1}",
+            summary.fps(), summary.low_1_percent_fps(), summary.low_0_1_percent_fps()),
+        format!("cpu ms: {}", phases),
+        gpu_text,
+    ];
+
+    let pixels = 10.0;
+    let line_spacing = pixels * 3.2;
+    let x = -1.0 + 40.0 / w;
+    for (i, line) in lines.iter().rev().enumerate() {
+        let y = -1.0 + (100.0 + i as f32 * line_spacing) / h;
+        text.draw_text(line, x, y, 2.0 * pixels / w, 2.0 * pixels / h);
+    }
+
+    // above the top line, which would take the next baseline, so the graph's
+    // background doesn't cover any of the text
+    let text_top = 100.0 + lines.len() as f32 * line_spacing;
+    let graph_width = 300.0;
+    let graph_height = 80.0;
+    stats.draw_graph(shapes, 20.0, h - text_top - graph_height, graph_width, graph_height);
+    shapes.flush(window_size);
+}
+
+// label styles cycled through with the L key
+const LABEL_MODES: [Option<labels::LabelOptions>;4] = [
+    None,
+    Some(labels::LabelOptions { size: labels::LabelSize::Pixels(16.0), billboard: true, centered: true }),
+    Some(labels::LabelOptions { size: labels::LabelSize::World(0.2), billboard: true, centered: true }),
+    Some(labels::LabelOptions { size: labels::LabelSize::World(0.2), billboard: false, centered: true }),
+];
+
+// grab and hide the cursor for mouse look, or let it go. returns whether it's grabbed.
+fn set_mouse_grab(gl_window: &glutin::GlWindow, grab: bool) -> bool {
+    if let Err(e) = gl_window.grab_cursor(grab) {
+        log::warn!("couldn't {} the cursor: {}", if grab { "grab" } else { "release" }, e);
+        return false;
+    }
+    gl_window.hide_cursor(grab);
+    grab
+}
+
+#[derive(Debug)]
 struct Texture {
-    pub id: u32,
+    pub texture: gl_object::Texture,
     pub uniform_location: i32,
     pub width: i32,
     pub height: i32,
 }
 
-#[derive(Debug, Clone, Copy)]
+#[derive(Debug)]
 struct VertexArray {
-    pub id: u32,
+    pub vertex_array: gl_object::VertexArray,
     vertex_count: i32,
+    // (min, max) corners of the box around the positions, before the transform
+    bounds: (cgmath::Point3<f32>, cgmath::Point3<f32>),
+    transform: transform::Transform,
+    // turns around this axis at a radian per second
+    spin_axis: Option<cgmath::Vector3<f32>>,
 }
 
-#[derive(Default)]
 struct GlData {
     textures: Vec<Texture>,
-    program: u32,
-    buffers: Vec<u32>,
+    program: gl_object::Program,
+    buffers: Vec<gl_object::Buffer>,
     vertex_arrays: Vec<VertexArray>,
 }
 
-impl Drop for GlData {
-    fn drop(&mut self) {
-        for e in self.textures.iter() {
-            unsafe {
-                gl::DeleteTextures(1, &e.id);
-            }
-        }
-
-        for e in self.vertex_arrays.iter() {
-            unsafe {
-                gl::DeleteVertexArrays(1, &e.id);
-            }
-        }
+impl GlData {
+    // the model matrix of each vertex array
+    fn model_matrices(&self) -> Vec<cgmath::Matrix4<f32>> {
+        let transforms = self.vertex_arrays.iter().map(|v| v.transform).collect::<Vec<_>>();
+        transform::model_matrices(&transforms)
+    }
 
-        unsafe {
-            gl::DeleteProgram(self.program);
-            gl::DeleteBuffers(self.buffers.len() as i32, self.buffers.as_ptr());
-        }
+    // the box around every vertex array where it is in the world, or None if there aren't any
+    fn bounds(&self) -> Option<(cgmath::Point3<f32>, cgmath::Point3<f32>)> {
+        let models = self.model_matrices();
+        self.vertex_arrays.iter().zip(models.iter())
+            .map(|(v, model)| transform::transform_bounds(model, v.bounds))
+            .fold(None, |total, (min, max)| match total {
+                None => Some((min, max)),
+                Some((tmin, tmax)) => Some((
+                    cgmath::Point3::new(tmin.x.min(min.x), tmin.y.min(min.y), tmin.z.min(min.z)),
+                    cgmath::Point3::new(tmax.x.max(max.x), tmax.y.max(max.y), tmax.z.max(max.z)))),
+            })
     }
 }
 
+// locations of the uniforms the scene program can use, each None if it doesn't.
+// normal_matrix takes normals to world space, like model does positions. view,
+// projection and time are also in the Frame block, see frame_uniforms, but are
+// still set for programs that declare them as plain uniforms instead.
 #[derive(Debug, Clone, Copy)]
-struct Camera {
-    position: cgmath::Point3<f32>,
-    angle: cgmath::Deg<f32>,
-}
-
-pub struct KeyStates {
-    pressed: [bool;188],
+struct SceneUniforms {
+    model: Option<i32>,
+    view: Option<i32>,
+    projection: Option<i32>,
+    normal_matrix: Option<i32>,
+    mvp: Option<i32>,
+    time: Option<i32>,
 }
 
-impl KeyStates {
-    pub fn new() -> KeyStates {
-        KeyStates {
-            pressed: [false;188],
+impl SceneUniforms {
+    fn find(program: u32) -> SceneUniforms {
+        let find = |name| gl_helpers::get_uniform_location(program, name).ok();
+        SceneUniforms {
+            model: find(cstr!("model")),
+            view: find(cstr!("view")),
+            projection: find(cstr!("projection")),
+            normal_matrix: find(cstr!("normal_matrix")),
+            mvp: find(cstr!("mvp")),
+            time: find(cstr!("time")),
         }
     }
-}
 
-impl std::ops::Index<glutin::VirtualKeyCode> for KeyStates {
-    type Output = bool;
-    fn index(&self, key: glutin::VirtualKeyCode) -> &bool {
-        &self.pressed[key as usize]
+    // upload the per object matrices, for the program that's in use
+    fn set_model(&self, model: &cgmath::Matrix4<f32>, view: &cgmath::Matrix4<f32>, projection: &cgmath::Matrix4<f32>) {
+        self.set_matrix4(self.model, model);
+        self.set_matrix4(self.mvp, &(projection * view * model));
+        if let Some(loc) = self.normal_matrix {
+            use cgmath::Matrix;
+            unsafe {
+                gl::UniformMatrix3fv(loc, 1, gl::FALSE, transform::normal_matrix(model).as_ptr());
+            }
+        }
     }
-}
 
-impl std::ops::IndexMut<glutin::VirtualKeyCode> for KeyStates {
-    fn index_mut(&mut self, key: glutin::VirtualKeyCode) -> &mut bool {
-        &mut self.pressed[key as usize]
+    fn set_matrix4(&self, location: Option<i32>, matrix: &cgmath::Matrix4<f32>) {
+        if let Some(loc) = location {
+            use cgmath::Matrix;
+            unsafe {
+                gl::UniformMatrix4fv(loc, 1, gl::FALSE, matrix.as_ptr());
+            }
+        }
     }
 }
\ No newline at end of file
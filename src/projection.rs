@@ -0,0 +1,151 @@
+// how the view is flattened onto the screen. perspective can have its far plane at
+// infinity, in which case reversed-z keeps depth precise all the way out: the depth
+// buffer holds 1 at the near plane falling towards 0 at infinity, which suits the
+// way floats are spaced much better than the usual 0 to 1.
+use crate::gl;
+use cgmath::Angle;
+
+// names of the modes, in the order the projection key cycles through them
+pub const MODES: [&str;3] = ["perspective", "reversed_z", "orthographic"];
+
+// keep the matrices finite whatever the cvars are set to
+const MIN_FOV: f32 = 1.0;
+const MAX_FOV: f32 = 179.0;
+const MIN_NEAR: f32 = 0.0001;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Projection {
+    Perspective {
+        // vertical field of view
+        fov: cgmath::Deg<f32>,
+        near: f32,
+        far: f32,
+    },
+    // perspective with no far plane
+    InfinitePerspective {
+        fov: cgmath::Deg<f32>,
+        near: f32,
+        reversed_z: bool,
+    },
+    Orthographic {
+        // world units from the bottom of the view to the top
+        height: f32,
+        near: f32,
+        far: f32,
+    },
+}
+
+// width over height, or 1 while either is 0, e.g. when the window is minimized
+pub fn aspect_ratio(window_size: (u32, u32)) -> f32 {
+    if window_size.0 == 0 || window_size.1 == 0 {
+        1.0
+    } else {
+        window_size.0 as f32 / window_size.1 as f32
+    }
+}
+
+impl Projection {
+    // the projection picked by the projection cvar, with an unknown name giving
+    // plain perspective. reversed-z needs clip control, and without it falls back
+    // to an infinite far plane with the usual depth range.
+    pub fn from_cvars(cvars: &crate::cvars::Cvars, clip_control: bool) -> Projection {
+        let fov = cgmath::Deg(cvars.float("fov"));
+        let near = cvars.float("near_plane");
+        let far = cvars.float("far_plane");
+        match cvars.string("projection") {
+            "reversed_z" => Projection::InfinitePerspective { fov, near, reversed_z: clip_control },
+            "orthographic" => Projection::Orthographic { height: cvars.float("ortho_height"), near, far },
+            _ => Projection::Perspective { fov, near, far },
+        }
+    }
+
+    pub fn matrix(&self, aspect: f32) -> cgmath::Matrix4<f32> {
+        let aspect = if aspect.is_finite() && aspect > 0.0 { aspect } else { 1.0 };
+        match *self {
+            Projection::Perspective { fov, near, far } => {
+                let (near, far) = clip_planes(near, far);
+                cgmath::perspective(clamp_fov(fov), aspect, near, far)
+            },
+
+            Projection::InfinitePerspective { fov, near, reversed_z } => {
+                let f = 1.0 / (clamp_fov(fov) * 0.5).tan();
+                let near = near.max(MIN_NEAR);
+                // the limits of the usual perspective matrix as far goes to infinity.
+                // reversed, clip z is just the near distance, so depth is near / distance.
+                let (zz, wz) = if reversed_z { (0.0, near) } else { (-1.0, -2.0 * near) };
+                cgmath::Matrix4::new(
+                    f / aspect, 0.0, 0.0, 0.0,
+                    0.0, f, 0.0, 0.0,
+                    0.0, 0.0, zz, -1.0,
+                    0.0, 0.0, wz, 0.0)
+            },
+
+            Projection::Orthographic { height, near, far } => {
+                let top = height.abs().max(MIN_NEAR) * 0.5;
+                let right = top * aspect;
+                let (near, far) = if far > near { (near, far) } else { (near, near + 1.0) };
+                cgmath::ortho(-right, right, -top, top, near, far)
+            },
+        }
+    }
+
+    // the vertical field of view, as the matrix uses it, or None for orthographic
+    pub fn fov(&self) -> Option<cgmath::Deg<f32>> {
+        match *self {
+            Projection::Perspective { fov, .. } | Projection::InfinitePerspective { fov, .. } => Some(clamp_fov(fov)),
+            Projection::Orthographic { .. } => None,
+        }
+    }
+
+    pub fn reversed_z(&self) -> bool {
+        match *self {
+            Projection::InfinitePerspective { reversed_z, .. } => reversed_z,
+            _ => false,
+        }
+    }
+
+    // world units covered by one pixel of a view `pixels` high, at `distance` in
+    // front of the camera
+    pub fn units_per_pixel(&self, distance: f32, pixels: u32) -> f32 {
+        let height = match *self {
+            Projection::Perspective { fov, .. } | Projection::InfinitePerspective { fov, .. } =>
+                2.0 * distance * (clamp_fov(fov) * 0.5).tan(),
+            Projection::Orthographic { height, .. } => height,
+        };
+        height / pixels.max(1) as f32
+    }
+
+    // set up depth testing for drawing with this projection. needs clip control
+    // support if the projection is reversed.
+    pub fn begin_depth(&self) {
+        if self.reversed_z() {
+            unsafe {
+                gl::ClipControl(gl::LOWER_LEFT, gl::ZERO_TO_ONE);
+                gl::ClearDepth(0.0);
+                gl::DepthFunc(gl::GREATER);
+            }
+        }
+    }
+
+    // go back to the usual depth range and test, and clear the depth buffer so
+    // screen space drawing that follows isn't hidden behind the scene
+    pub fn end_depth(&self) {
+        if self.reversed_z() {
+            unsafe {
+                gl::ClipControl(gl::LOWER_LEFT, gl::NEGATIVE_ONE_TO_ONE);
+                gl::ClearDepth(1.0);
+                gl::DepthFunc(gl::LESS);
+                gl::Clear(gl::DEPTH_BUFFER_BIT);
+            }
+        }
+    }
+}
+
+fn clamp_fov(fov: cgmath::Deg<f32>) -> cgmath::Deg<f32> {
+    cgmath::Deg(fov.0.clamp(MIN_FOV, MAX_FOV))
+}
+
+fn clip_planes(near: f32, far: f32) -> (f32, f32) {
+    let near = near.max(MIN_NEAR);
+    (near, far.max(near * 2.0))
+}
@@ -0,0 +1,23 @@
+use crate::gl;
+
+// save the current contents of the back buffer as a png, returning the file name
+pub fn save(window_size: (u32, u32)) -> Result<String, Box<dyn std::error::Error>> {
+    let (w, h) = window_size;
+    let mut pixels = vec![0u8; (w * h * 4) as usize];
+    unsafe {
+        gl::PixelStorei(gl::PACK_ALIGNMENT, 1);
+        gl::ReadPixels(0, 0, w as i32, h as i32, gl::RGBA, gl::UNSIGNED_BYTE,
+            pixels.as_mut_ptr() as *mut std::os::raw::c_void);
+    }
+
+    let image = image::RgbaImage::from_raw(w, h, pixels)
+        .ok_or_else(|| crate::Error::new("screenshot buffer has the wrong size"))?;
+
+    // gl's first row is the bottom of the window
+    let image = image::imageops::flip_vertical(&image);
+
+    let seconds = std::time::SystemTime::now().duration_since(std::time::UNIX_EPOCH)?.as_secs();
+    let path = format!("screenshot-{}.png", seconds);
+    image.save(&path)?;
+    Ok(path)
+}
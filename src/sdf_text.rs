@@ -0,0 +1,231 @@
+use crate::gl;
+use crate::gl_object;
+use crate::glsl;
+use crate::glyph_atlas::GlyphAtlas;
+use crate::text_renderer::TextRenderer;
+use rusttype::Font;
+
+// the distance field is stored at this pixel height, and computed from
+// a rasterization SUPERSAMPLE times larger
+const PIXEL_HEIGHT: f32 = 32.0;
+const SUPERSAMPLE: i32 = 4;
+
+// distance in atlas pixels covered by the 0..1 range of the field, on each side of the edge
+const SPREAD: f32 = 4.0;
+
+const VERTEX_SHADER_CODE: &str = r"
+layout (location=0) in vec2 position;
+layout (location=1) in vec2 tex_coords;
+uniform mat4 transform;
+out vec2 v_tex_coords;
+void main() {
+    gl_Position = transform * vec4(position, 0.0, 1.0);
+    v_tex_coords = tex_coords;
+}
+";
+
+const FRAGMENT_SHADER_CODE: &str = r"
+in vec2 v_tex_coords;
+out vec4 f_color;
+uniform sampler2D atlas;
+uniform float threshold;
+void main() {
+    float distance = texture(atlas, v_tex_coords).r;
+    float width = fwidth(distance);
+    float alpha = smoothstep(threshold - width, threshold + width, distance);
+    f_color = vec4(1.0, 1.0, 1.0, alpha);
+}
+";
+
+pub struct SdfTextContext {
+    atlas: GlyphAtlas,
+    weight: f32,
+    program: gl_object::Program,
+    buffer: gl_object::Buffer,
+    vao: gl_object::VertexArray,
+    texture: gl_object::Texture,
+    transform_uniform_location: i32,
+    threshold_uniform_location: i32,
+}
+
+impl SdfTextContext {
+    pub fn from_file<P: AsRef<std::path::Path>>(path: P) -> Result<SdfTextContext, Box<dyn std::error::Error>> {
+        let bytes = std::fs::read(path)?;
+        let font = Font::from_bytes(bytes)?;
+        SdfTextContext::new(font)
+    }
+
+    pub fn new(font: Font<'static>) -> Result<SdfTextContext, Box<dyn std::error::Error>> {
+        let border = (SPREAD as i32 + 1) * SUPERSAMPLE;
+        let atlas = GlyphAtlas::new(font, PIXEL_HEIGHT * SUPERSAMPLE as f32, border, SUPERSAMPLE, signed_distance_field);
+
+        let (program, _) = glsl::build_program("sdf text", VERTEX_SHADER_CODE, FRAGMENT_SHADER_CODE)?;
+
+        let transform_uniform_location = glsl::uniform_location(&program, "transform")?;
+        let threshold_uniform_location = glsl::uniform_location(&program, "threshold")?;
+        let atlas_uniform_location = glsl::uniform_location(&program, "atlas")?;
+
+        let texture = atlas.create_texture();
+        unsafe {
+            gl::UseProgram(program.id());
+            gl::Uniform1i(atlas_uniform_location, 0);
+        }
+
+        let buffer = gl_object::Buffer::new();
+        let vao = gl_helpers::create_single_buffer_vertex_array(buffer.id(), &[2, 2])?;
+        let vao = gl_object::VertexArray::from_raw(vao);
+
+        Ok(SdfTextContext {
+            atlas,
+            weight: 0.0,
+            program,
+            buffer,
+            vao,
+            texture,
+            transform_uniform_location,
+            threshold_uniform_location,
+        })
+    }
+
+    // the field value where the glyph edge lies, moved outward by half the extra weight
+    fn threshold(&self) -> f32 {
+        let units_per_texel = self.atlas.units_per_pixel() * SUPERSAMPLE as f32;
+        let texels = 0.5 * self.weight / units_per_texel;
+        (0.5 - texels / (2.0 * SPREAD)).clamp(0.0, 1.0)
+    }
+}
+
+impl TextRenderer for SdfTextContext {
+    fn name(&self) -> &str {
+        "sdf"
+    }
+
+    fn weight(&self) -> f32 {
+        self.weight
+    }
+
+    fn set_weight(&mut self, weight: f32) {
+        self.weight = weight;
+    }
+
+    fn measure(&self, string: &str) -> f32 {
+        self.atlas.measure(string)
+    }
+
+    fn draw_text_transformed(&self, string: &str, transform: &cgmath::Matrix4<f32>) {
+        let vertices = self.atlas.build_vertices(string);
+        if vertices.is_empty() {
+            return;
+        }
+
+        unsafe {
+            use cgmath::Matrix;
+            gl::UseProgram(self.program.id());
+            gl::UniformMatrix4fv(self.transform_uniform_location, 1, gl::FALSE, transform.as_ptr());
+            gl::Uniform1f(self.threshold_uniform_location, self.threshold());
+
+            gl::BindBuffer(gl::ARRAY_BUFFER, self.buffer.id());
+            gl::BufferData(gl::ARRAY_BUFFER,
+                (vertices.len() * std::mem::size_of::<f32>()) as isize,
+                vertices.as_ptr() as *const std::os::raw::c_void,
+                gl::DYNAMIC_DRAW);
+
+            gl::ActiveTexture(gl::TEXTURE0);
+            gl::BindTexture(gl::TEXTURE_2D, self.texture.id());
+
+            gl::Enable(gl::BLEND);
+            gl::BlendFunc(gl::SRC_ALPHA, gl::ONE_MINUS_SRC_ALPHA);
+            gl::DepthMask(gl::FALSE);
+
+            gl::BindVertexArray(self.vao.id());
+            gl::DrawArrays(gl::TRIANGLES, 0, (vertices.len() / 4) as i32);
+
+            gl::DepthMask(gl::TRUE);
+            gl::Disable(gl::BLEND);
+        }
+    }
+}
+
+const FAR: f64 = 1e20;
+
+// exact squared euclidean distance transform of a sampled function, in one dimension
+// (Felzenszwalb & Huttenlocher). v and z are scratch space of at least f.len() and f.len()+1.
+fn distance_transform_1d(f: &[f64], d: &mut [f64], v: &mut [usize], z: &mut [f64]) {
+    let parabola_intersection = |q: usize, p: usize| {
+        let (qf, pf) = (q as f64, p as f64);
+        ((f[q] + qf * qf) - (f[p] + pf * pf)) / (2.0 * qf - 2.0 * pf)
+    };
+
+    let mut k = 0;
+    v[0] = 0;
+    z[0] = -FAR;
+    z[1] = FAR;
+    for q in 1..f.len() {
+        let mut s = parabola_intersection(q, v[k]);
+        while s <= z[k] {
+            k -= 1;
+            s = parabola_intersection(q, v[k]);
+        }
+        k += 1;
+        v[k] = q;
+        z[k] = s;
+        z[k + 1] = FAR;
+    }
+
+    k = 0;
+    for (q, dq) in d.iter_mut().enumerate().take(f.len()) {
+        while z[k + 1] < q as f64 {
+            k += 1;
+        }
+        let offset = q as f64 - v[k] as f64;
+        *dq = offset * offset + f[v[k]];
+    }
+}
+
+// squared distance from every pixel to the nearest pixel where `grid` is zero
+fn distance_transform_2d(grid: &mut [f64], w: usize, h: usize) {
+    let n = w.max(h);
+    let (mut f, mut d) = (vec![0.0; n], vec![0.0; n]);
+    let (mut v, mut z) = (vec![0; n], vec![0.0; n + 1]);
+
+    for x in 0..w {
+        for (y, fy) in f.iter_mut().enumerate().take(h) {
+            *fy = grid[y * w + x];
+        }
+        distance_transform_1d(&f[..h], &mut d[..h], &mut v, &mut z);
+        for (y, dy) in d.iter().enumerate().take(h) {
+            grid[y * w + x] = *dy;
+        }
+    }
+
+    for y in 0..h {
+        f[..w].copy_from_slice(&grid[y * w..(y + 1) * w]);
+        distance_transform_1d(&f[..w], &mut d[..w], &mut v, &mut z);
+        grid[y * w..(y + 1) * w].copy_from_slice(&d[..w]);
+    }
+}
+
+// turn a coverage raster into a distance field SUPERSAMPLE times smaller, where 0.5 is
+// the glyph edge and values increase towards the inside
+fn signed_distance_field(raster: &[u8], w: i32, h: i32) -> Vec<u8> {
+    let (w, h) = (w as usize, h as usize);
+    let inside = |i: usize| raster[i] >= 128;
+
+    let mut to_inside: Vec<f64> = (0..w * h).map(|i| if inside(i) { 0.0 } else { FAR }).collect();
+    let mut to_outside: Vec<f64> = (0..w * h).map(|i| if inside(i) { FAR } else { 0.0 }).collect();
+    distance_transform_2d(&mut to_inside, w, h);
+    distance_transform_2d(&mut to_outside, w, h);
+
+    let ss = SUPERSAMPLE as usize;
+    let (out_w, out_h) = (w / ss, h / ss);
+    let mut field = Vec::with_capacity(out_w * out_h);
+    for y in 0..out_h {
+        for x in 0..out_w {
+            let i = (y * ss + ss / 2) * w + (x * ss + ss / 2);
+            let distance = (to_outside[i].sqrt() - to_inside[i].sqrt()) / SUPERSAMPLE as f64;
+            let value = 0.5 + distance / (2.0 * SPREAD as f64);
+            field.push((value.clamp(0.0, 1.0) * 255.0).round() as u8);
+        }
+    }
+    field
+}
@@ -0,0 +1,112 @@
+// batches flat colored rectangles and lines given in window pixel coordinates
+// (origin at the top left), then draws them all at once
+use crate::gl;
+use crate::gl_object;
+use crate::glsl;
+use crate::Error;
+
+pub type Color = [f32;4];
+
+const VERTEX_SHADER_CODE: &str = r"
+layout (location=0) in vec2 position;
+layout (location=1) in vec4 color;
+uniform vec2 window_size;
+out vec4 v_color;
+void main() {
+    vec2 clip = position / window_size * 2.0 - 1.0;
+    gl_Position = vec4(clip.x, -clip.y, -1.0, 1.0);
+    v_color = color;
+}
+";
+
+const FRAGMENT_SHADER_CODE: &str = r"
+in vec4 v_color;
+out vec4 f_color;
+void main() {
+    f_color = v_color;
+}
+";
+
+// floats per vertex: position then color
+const VERTEX_SIZE: usize = 6;
+
+pub struct ShapeRenderer {
+    program: gl_object::Program,
+    buffer: gl_object::Buffer,
+    vao: gl_object::VertexArray,
+    window_size_uniform_location: i32,
+    triangles: Vec<f32>,
+    lines: Vec<f32>,
+}
+
+impl ShapeRenderer {
+    pub fn new() -> Result<ShapeRenderer, Error> {
+        let (program, _) = glsl::build_program("shape", VERTEX_SHADER_CODE, FRAGMENT_SHADER_CODE)?;
+        let window_size_uniform_location = glsl::uniform_location(&program, "window_size")?;
+
+        let buffer = gl_object::Buffer::new();
+        let vao = gl_helpers::create_single_buffer_vertex_array(buffer.id(), &[2, 4])
+            .map_err(|e| Error::new(format!("couldn't create shape vertex array: {}", e)))?;
+        let vao = gl_object::VertexArray::from_raw(vao);
+
+        Ok(ShapeRenderer {
+            program,
+            buffer,
+            vao,
+            window_size_uniform_location,
+            triangles: Vec::new(),
+            lines: Vec::new(),
+        })
+    }
+
+    pub fn rect(&mut self, x: f32, y: f32, w: f32, h: f32, color: Color) {
+        let (x1, y1) = (x + w, y + h);
+        for &(px, py) in [(x, y), (x1, y), (x1, y1), (x, y), (x1, y1), (x, y1)].iter() {
+            self.triangles.extend_from_slice(&[px, py]);
+            self.triangles.extend_from_slice(&color);
+        }
+    }
+
+    pub fn line(&mut self, from: [f32;2], to: [f32;2], color: Color) {
+        for point in [from, to].iter() {
+            self.lines.extend_from_slice(point);
+            self.lines.extend_from_slice(&color);
+        }
+    }
+
+    pub fn line_strip(&mut self, points: &[[f32;2]], color: Color) {
+        for pair in points.windows(2) {
+            self.line(pair[0], pair[1], color);
+        }
+    }
+
+    // draw everything added since the last flush, over the top of the scene
+    pub fn flush(&mut self, window_size: (u32, u32)) {
+        unsafe {
+            gl::UseProgram(self.program.id());
+            gl::Uniform2f(self.window_size_uniform_location, window_size.0 as f32, window_size.1 as f32);
+            gl::BindVertexArray(self.vao.id());
+            gl::BindBuffer(gl::ARRAY_BUFFER, self.buffer.id());
+            gl::Enable(gl::BLEND);
+            gl::BlendFunc(gl::SRC_ALPHA, gl::ONE_MINUS_SRC_ALPHA);
+            gl::Disable(gl::DEPTH_TEST);
+
+            for (vertices, mode) in [(&self.triangles, gl::TRIANGLES), (&self.lines, gl::LINES)].iter() {
+                if vertices.is_empty() {
+                    continue;
+                }
+                gl::BufferData(gl::ARRAY_BUFFER,
+                    (vertices.len() * std::mem::size_of::<f32>()) as isize,
+                    vertices.as_ptr() as *const std::os::raw::c_void,
+                    gl::STREAM_DRAW);
+                gl::DrawArrays(*mode, 0, (vertices.len() / VERTEX_SIZE) as i32);
+            }
+
+            gl::Enable(gl::DEPTH_TEST);
+            gl::Disable(gl::BLEND);
+        }
+
+        self.triangles.clear();
+        self.lines.clear();
+    }
+}
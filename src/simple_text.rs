@@ -1,10 +1,8 @@
 use crate::gl;
-
-macro_rules! cstr {
-    ($e:expr) => {
-        concat!($e, "\0").as_ptr() as *const std::os::raw::c_char
-    };
-}
+use crate::gl_object;
+use crate::glsl;
+use crate::text_renderer::TextRenderer;
+use crate::Error;
 
 // internal vertex
 type Vertex = [f32;2];
@@ -91,93 +89,152 @@ const LETTERS: [[i32;15];NUM_CHARS] = [
 ];
 
 const VERTEX_SHADER_CODE: &str = r"
-#version 430 core
 layout (location=0) in vec2 position;
+uniform mat4 transform;
 uniform vec2 offset;
-uniform vec2 scale;
 void main() {
-    float x = position.x * scale.x + offset.x;
-    float y = position.y * scale.y + offset.y;
-    gl_Position = vec4(x, y, -1.0, 1.0);
+    gl_Position = transform * vec4(position + offset, 0.0, 1.0);
 }
 ";
 
 const FRAGMENT_SHADER_CODE: &str = r"
-#version 430 core
 out vec4 f_color;
 void main() {
     f_color = vec4(1.0, 1.0, 1.0, 1.0);
 }
 ";
 
-#[derive(Clone, Copy)]
+// horizontal distance between letters, in text units
+const ADVANCE: f32 = 0.675;
+
 pub struct VertexArray {
-    pub buffer: u32,
-    pub vao: u32,
+    pub buffer: gl_object::Buffer,
+    pub vao: gl_object::VertexArray,
     pub vcount: i32,
 }
 
 pub struct SimpleTextContext {
-    program: u32,
+    program: gl_object::Program,
     vertex_arrays: Vec<VertexArray>,
+    stroke_weight: f32,
+    transform_uniform_location: i32,
     offset_uniform_location: i32,
-    scale_uniform_location: i32,
 }
 
 impl SimpleTextContext {
-    pub fn new() -> SimpleTextContext {
-        let program = {
-            let vcode = VERTEX_SHADER_CODE;
-            let fcode = FRAGMENT_SHADER_CODE;
-            gl_helpers::ProgramBuilder::new()
-                .vertex_shader_code(vcode)
-                .fragment_shader_code(fcode)
-                .build()
-                .unwrap()
-        };
+    pub fn new() -> Result<SimpleTextContext, Error> {
+        let (program, _) = glsl::build_program("stroke text", VERTEX_SHADER_CODE, FRAGMENT_SHADER_CODE)?;
 
-        let offset_uniform_location = gl_helpers::get_uniform_location(program, cstr!("offset")).unwrap();
-        let scale_uniform_location = gl_helpers::get_uniform_location(program, cstr!("scale")).unwrap();
-
-        let mut vertex_arrays = Vec::new();
-        for i in 0..(NUM_CHARS) {
-            let vcount = LETTERS[i][0];
-            let vertices: Vec<f32> = LETTERS[i][1..].iter().take(vcount as usize).flat_map(
-                |index| VERTS[*index as usize].iter().copied()
-            ).collect();
-            let buffer = gl_helpers::create_buffer(&vertices, gl_helpers::BufferUsage::StaticDraw).unwrap();
-            let vao = gl_helpers::create_single_buffer_vertex_array(buffer, &[2]).unwrap();
-            vertex_arrays.push(VertexArray { buffer, vao, vcount });
-        }
+        let transform_uniform_location = glsl::uniform_location(&program, "transform")?;
+        let offset_uniform_location = glsl::uniform_location(&program, "offset")?;
 
-        SimpleTextContext {
+        Ok(SimpleTextContext {
             program,
-            vertex_arrays,
+            vertex_arrays: create_vertex_arrays(0.0)?,
+            stroke_weight: 0.0,
+            transform_uniform_location,
             offset_uniform_location,
-            scale_uniform_location,
+        })
+    }
+}
+
+// with a weight of zero, letters are drawn as 1 pixel gl::LINES.
+// otherwise each line segment becomes a quad `weight` text units thick.
+fn create_vertex_arrays(weight: f32) -> Result<Vec<VertexArray>, Error> {
+    let mut vertex_arrays = Vec::new();
+    for i in 0..(NUM_CHARS) {
+        let vcount = LETTERS[i][0];
+        let indices = &LETTERS[i][1..(1 + vcount as usize)];
+        let vertices: Vec<f32> = if weight > 0.0 {
+            indices.chunks(2).flat_map(
+                |segment| segment_quad(VERTS[segment[0] as usize], VERTS[segment[1] as usize], weight)
+            ).collect()
+        } else {
+            indices.iter().flat_map(
+                |index| VERTS[*index as usize].iter().copied()
+            ).collect()
+        };
+        let vcount = vertices.len() as i32 / 2;
+        let buffer = gl_helpers::create_buffer(&vertices, gl_helpers::BufferUsage::StaticDraw)
+            .map_err(|e| Error::new(format!("couldn't create stroke text vertex buffer: {}", e)))?;
+        let buffer = gl_object::Buffer::from_raw(buffer);
+        let vao = gl_helpers::create_single_buffer_vertex_array(buffer.id(), &[2])
+            .map_err(|e| Error::new(format!("couldn't create stroke text vertex array: {}", e)))?;
+        let vao = gl_object::VertexArray::from_raw(vao);
+        vertex_arrays.push(VertexArray { buffer, vao, vcount });
+    }
+    Ok(vertex_arrays)
+}
+
+// two triangles covering the segment from a to b, extended by half the
+// weight past each end so that the corners of connected segments meet
+fn segment_quad(a: Vertex, b: Vertex, weight: f32) -> Vec<f32> {
+    let half = weight * 0.5;
+    let (dx, dy) = (b[0] - a[0], b[1] - a[1]);
+    let length = (dx * dx + dy * dy).sqrt();
+    if length == 0.0 {
+        return Vec::new();
+    }
+    let (dx, dy) = (dx / length * half, dy / length * half);
+    let (nx, ny) = (-dy, dx);
+
+    let a = [a[0] - dx, a[1] - dy];
+    let b = [b[0] + dx, b[1] + dy];
+    vec![
+        a[0] + nx, a[1] + ny,   a[0] - nx, a[1] - ny,   b[0] - nx, b[1] - ny,
+        a[0] + nx, a[1] + ny,   b[0] - nx, b[1] - ny,   b[0] + nx, b[1] + ny,
+    ]
+}
+
+impl TextRenderer for SimpleTextContext {
+    fn name(&self) -> &str {
+        "stroke"
+    }
+
+    fn measure(&self, string: &str) -> f32 {
+        string.chars().count() as f32 * ADVANCE
+    }
+
+    fn weight(&self) -> f32 {
+        self.stroke_weight
+    }
+
+    fn set_weight(&mut self, weight: f32) {
+        let weight = weight.max(0.0);
+        if weight != self.stroke_weight {
+            match create_vertex_arrays(weight) {
+                Ok(vertex_arrays) => {
+                    self.vertex_arrays = vertex_arrays;
+                    self.stroke_weight = weight;
+                },
+                Err(e) => log::warn!("couldn't change stroke text weight: {}", e),
+            }
         }
     }
 
-    pub fn draw_text(&self, string: &str, x: f32, y: f32, xscale: f32, yscale: f32) {
+    fn draw_text_transformed(&self, string: &str, transform: &cgmath::Matrix4<f32>) {
+        let mode = if self.stroke_weight > 0.0 { gl::TRIANGLES } else { gl::LINES };
+
         unsafe {
-            gl::UseProgram(self.program);
-            gl::Uniform2f(self.scale_uniform_location, xscale, yscale);
+            use cgmath::Matrix;
+            gl::UseProgram(self.program.id());
+            gl::UniformMatrix4fv(self.transform_uniform_location, 1, gl::FALSE, transform.as_ptr());
         }
 
-        let mut x = x;
+        let mut x = 0.0;
         for c in string.chars() {
             let index = c.to_ascii_uppercase() as usize;
 
             if index >= START_CHAR && index <= END_CHAR {
                 let index = index - START_CHAR;
                 unsafe {
-                    gl::Uniform2f(self.offset_uniform_location, x, y);
-                    gl::BindVertexArray(self.vertex_arrays[index].vao);
-                    gl::DrawArrays(gl::LINES, 0, self.vertex_arrays[index].vcount);
+                    gl::Uniform2f(self.offset_uniform_location, x, 0.0);
+                    gl::BindVertexArray(self.vertex_arrays[index].vao.id());
+                    gl::DrawArrays(mode, 0, self.vertex_arrays[index].vcount);
                 }
             }
 
-            x += xscale * 0.675;
+            x += ADVANCE;
         }
     }
-}
\ No newline at end of file
+}
@@ -0,0 +1,28 @@
+// common interface for the text backends, so the one in use can be switched at runtime
+pub trait TextRenderer {
+    fn name(&self) -> &str;
+
+    // draw a string laid out in text units, where a capital letter is about one unit tall
+    // and the baseline starts at the origin, then moved into clip space by `transform`
+    fn draw_text_transformed(&self, string: &str, transform: &cgmath::Matrix4<f32>);
+
+    // width of `string` in text units
+    fn measure(&self, string: &str) -> f32;
+
+    // extra line thickness in text units. renderers that can't vary it ignore this.
+    fn weight(&self) -> f32 {
+        0.0
+    }
+
+    fn set_weight(&mut self, weight: f32) {
+        let _ = weight;
+    }
+
+    // draw a string with its baseline starting at (x, y) in clip space.
+    // xscale and yscale are the size of one text unit in clip space units.
+    fn draw_text(&self, string: &str, x: f32, y: f32, xscale: f32, yscale: f32) {
+        let transform = cgmath::Matrix4::from_translation(cgmath::Vector3::new(x, y, -1.0))
+            * cgmath::Matrix4::from_nonuniform_scale(xscale, yscale, 1.0);
+        self.draw_text_transformed(string, &transform);
+    }
+}
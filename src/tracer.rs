@@ -0,0 +1,149 @@
+// records timed scopes and frame markers, and writes them out as a chrome
+// `trace_event` json file that chrome://tracing and perfetto can open.
+// nothing is recorded, and scopes cost next to nothing, unless recording.
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Mutex;
+use std::time::Instant;
+
+// if this many events pile up, the trace ends at the next frame marker and is
+// saved with what it has, rather than using up memory
+const MAX_EVENTS: usize = 1000000;
+
+struct Event {
+    name: &'static str,
+    // microseconds since recording started
+    timestamp: f64,
+    // None for instant events such as frame markers
+    duration: Option<f64>,
+    arg: Option<(&'static str, i64)>,
+}
+
+struct Recording {
+    start: Instant,
+    events: Vec<Event>,
+    // frame markers still to record before the trace is done
+    frames_left: u64,
+    // whether it hit MAX_EVENTS and was ended early
+    truncated: bool,
+}
+
+static RECORDING: AtomicBool = AtomicBool::new(false);
+static STATE: Mutex<Option<Recording>> = Mutex::new(None);
+
+pub fn is_recording() -> bool {
+    RECORDING.load(Ordering::Relaxed)
+}
+
+// start recording `frames` frames, throwing away anything recorded and not saved.
+// recording carries on across a restart of main_1, so resource loading can be traced.
+pub fn start(frames: u64) {
+    if let Ok(mut state) = STATE.lock() {
+        *state = Some(Recording { start: Instant::now(), events: Vec::new(), frames_left: frames, truncated: false });
+        RECORDING.store(true, Ordering::Relaxed);
+    }
+}
+
+fn record(name: &'static str, begin: Instant, duration: Option<f64>, arg: Option<(&'static str, i64)>) {
+    if let Ok(mut state) = STATE.lock() {
+        if let Some(recording) = state.as_mut() {
+            if recording.events.len() == MAX_EVENTS {
+                recording.truncated = true;
+                recording.frames_left = 0;
+                return;
+            }
+            let timestamp = begin.saturating_duration_since(recording.start).as_nanos() as f64 / 1000.0;
+            recording.events.push(Event { name, timestamp, duration, arg });
+        }
+    }
+}
+
+// a marker at the start of each frame, so frames can be told apart in the viewer.
+// returns true once the requested frames have been recorded and the trace should be saved.
+pub fn frame(number: u64) -> bool {
+    if !is_recording() {
+        return false;
+    }
+
+    let done = match STATE.lock() {
+        Ok(mut state) => match state.as_mut() {
+            Some(recording) if recording.frames_left > 0 => {
+                recording.frames_left -= 1;
+                false
+            },
+            _ => true,
+        },
+        Err(_) => true,
+    };
+    if !done {
+        record("frame", Instant::now(), None, Some(("frame", number as i64)));
+    }
+    done
+}
+
+// times everything until it's dropped
+pub struct Scope {
+    name: &'static str,
+    begin: Option<Instant>,
+    arg: Option<(&'static str, i64)>,
+}
+
+impl Scope {
+    // attach a number to the scope, shown in the viewer's args for it
+    pub fn arg(mut self, name: &'static str, value: i64) -> Scope {
+        self.arg = Some((name, value));
+        self
+    }
+}
+
+impl Drop for Scope {
+    fn drop(&mut self) {
+        if let Some(begin) = self.begin {
+            let duration = (Instant::now() - begin).as_nanos() as f64 / 1000.0;
+            record(self.name, begin, Some(duration), self.arg);
+        }
+    }
+}
+
+pub fn scope(name: &'static str) -> Scope {
+    let begin = if is_recording() { Some(Instant::now()) } else { None };
+    Scope { name, begin, arg: None }
+}
+
+// stop recording and write everything recorded to `trace-<seconds>.json`,
+// returning the file name
+pub fn save() -> Result<String, Box<dyn std::error::Error>> {
+    RECORDING.store(false, Ordering::Relaxed);
+    let recording = STATE.lock().map_err(|_| crate::Error::new("trace state is poisoned"))?.take()
+        .ok_or_else(|| crate::Error::new("no trace was recorded"))?;
+    if recording.truncated {
+        log::warn!("the trace reached {} events, so it was cut short", MAX_EVENTS);
+    }
+
+    let pid = std::process::id();
+    let mut json = String::from("{\"traceEvents\":[\n");
+    for (index, event) in recording.events.iter().enumerate() {
+        if index > 0 {
+            json.push_str(",\n");
+        }
+        json.push_str(&format!("{{\"name\":\"{}\",\"cat\":\"main\",\"pid\":{},\"tid\":1,\"ts\":{:.3}",
+            escape(event.name), pid, event.timestamp));
+        match event.duration {
+            Some(duration) => json.push_str(&format!(",\"ph\":\"X\",\"dur\":{:.3}", duration)),
+            None => json.push_str(",\"ph\":\"i\",\"s\":\"g\""),
+        }
+        if let Some((name, value)) = event.arg {
+            json.push_str(&format!(",\"args\":{{\"{}\":{}}}", escape(name), value));
+        }
+        json.push('}');
+    }
+    json.push_str("\n],\"displayTimeUnit\":\"ms\"}\n");
+
+    let seconds = std::time::SystemTime::now().duration_since(std::time::UNIX_EPOCH)?.as_secs();
+    let path = format!("trace-{}.json", seconds);
+    std::fs::write(&path, json)?;
+    Ok(path)
+}
+
+fn escape(text: &str) -> String {
+    text.replace('\\', "\\\\").replace('"', "\\\"")
+}
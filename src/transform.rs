@@ -0,0 +1,160 @@
+// where a drawable sits in the world. each transform is relative to its parent's,
+// if it has one, so moving a parent carries its children along.
+use cgmath::{Matrix, SquareMatrix};
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Transform {
+    pub translation: cgmath::Vector3<f32>,
+    pub rotation: cgmath::Quaternion<f32>,
+    pub scale: cgmath::Vector3<f32>,
+    // index of the parent in the same list of transforms
+    pub parent: Option<usize>,
+}
+
+impl Default for Transform {
+    fn default() -> Transform {
+        Transform {
+            translation: cgmath::Vector3::new(0.0, 0.0, 0.0),
+            rotation: cgmath::Quaternion::new(1.0, 0.0, 0.0, 0.0),
+            scale: cgmath::Vector3::new(1.0, 1.0, 1.0),
+            parent: None,
+        }
+    }
+}
+
+impl Transform {
+    // scale, then rotate, then translate, relative to the parent
+    pub fn local_matrix(&self) -> cgmath::Matrix4<f32> {
+        cgmath::Matrix4::from_translation(self.translation)
+            * cgmath::Matrix4::from(self.rotation)
+            * cgmath::Matrix4::from_nonuniform_scale(self.scale.x, self.scale.y, self.scale.z)
+    }
+}
+
+// the model matrix of each transform, taking in its parents. a parent that's out
+// of range or that leads back round to the child is treated as no parent.
+pub fn model_matrices(transforms: &[Transform]) -> Vec<cgmath::Matrix4<f32>> {
+    let mut matrices: Vec<Option<cgmath::Matrix4<f32>>> = vec![None; transforms.len()];
+    for index in 0..transforms.len() {
+        if matrices[index].is_some() {
+            continue;
+        }
+
+        // climb until reaching a parent that's done, or the top
+        let mut chain = vec![index];
+        loop {
+            let last = chain[chain.len() - 1];
+            match transforms[last].parent {
+                Some(parent) if parent < transforms.len() && matrices[parent].is_none() && !chain.contains(&parent) =>
+                    chain.push(parent),
+                _ => break,
+            }
+        }
+
+        let top = chain[chain.len() - 1];
+        let mut matrix = transforms[top].parent
+            .and_then(|parent| matrices.get(parent).cloned())
+            .and_then(|m| m)
+            .unwrap_or_else(cgmath::Matrix4::identity);
+        for &link in chain.iter().rev() {
+            matrix = matrix * transforms[link].local_matrix();
+            matrices[link] = Some(matrix);
+        }
+    }
+    matrices.into_iter().map(|m| m.unwrap_or_else(cgmath::Matrix4::identity)).collect()
+}
+
+// the matrix that takes normals to the same space `matrix` takes positions to, so
+// they stay at right angles to surfaces under non-uniform scaling
+pub fn normal_matrix(matrix: &cgmath::Matrix4<f32>) -> cgmath::Matrix3<f32> {
+    let upper = cgmath::Matrix3::from_cols(matrix.x.truncate(), matrix.y.truncate(), matrix.z.truncate());
+    upper.invert().map_or(upper, |inverse| inverse.transpose())
+}
+
+// the box around the (min, max) box once it's transformed by `matrix`
+pub fn transform_bounds(matrix: &cgmath::Matrix4<f32>, bounds: (cgmath::Point3<f32>, cgmath::Point3<f32>))
+    -> (cgmath::Point3<f32>, cgmath::Point3<f32>)
+{
+    use cgmath::Transform as _;
+    let (min, max) = bounds;
+    let mut result = (
+        cgmath::Point3::new(f32::MAX, f32::MAX, f32::MAX),
+        cgmath::Point3::new(f32::MIN, f32::MIN, f32::MIN));
+    for corner in 0..8 {
+        let point = matrix.transform_point(cgmath::Point3::new(
+            if corner & 1 == 0 { min.x } else { max.x },
+            if corner & 2 == 0 { min.y } else { max.y },
+            if corner & 4 == 0 { min.z } else { max.z }));
+        result.0 = cgmath::Point3::new(result.0.x.min(point.x), result.0.y.min(point.y), result.0.z.min(point.z));
+        result.1 = cgmath::Point3::new(result.1.x.max(point.x), result.1.y.max(point.y), result.1.z.max(point.z));
+    }
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn translated(x: f32, y: f32, z: f32, parent: Option<usize>) -> Transform {
+        Transform { translation: cgmath::Vector3::new(x, y, z), parent, ..Transform::default() }
+    }
+
+    fn translation(matrix: &cgmath::Matrix4<f32>) -> cgmath::Vector3<f32> {
+        matrix.w.truncate()
+    }
+
+    #[test]
+    fn children_follow_their_parents() {
+        let transforms = [
+            translated(1.0, 0.0, 0.0, None),
+            translated(0.0, 2.0, 0.0, Some(0)),
+            translated(0.0, 0.0, 3.0, Some(1)),
+        ];
+        let matrices = model_matrices(&transforms);
+        assert_eq!(translation(&matrices[2]), cgmath::Vector3::new(1.0, 2.0, 3.0));
+    }
+
+    #[test]
+    fn children_can_come_before_their_parents() {
+        let transforms = [
+            translated(0.0, 0.0, 3.0, Some(1)),
+            translated(0.0, 2.0, 0.0, Some(2)),
+            translated(1.0, 0.0, 0.0, None),
+        ];
+        let matrices = model_matrices(&transforms);
+        assert_eq!(translation(&matrices[0]), cgmath::Vector3::new(1.0, 2.0, 3.0));
+        assert_eq!(translation(&matrices[1]), cgmath::Vector3::new(1.0, 2.0, 0.0));
+        assert_eq!(translation(&matrices[2]), cgmath::Vector3::new(1.0, 0.0, 0.0));
+    }
+
+    #[test]
+    fn out_of_range_parents_are_ignored() {
+        let transforms = [translated(1.0, 2.0, 3.0, Some(7))];
+        assert_eq!(model_matrices(&transforms), vec![transforms[0].local_matrix()]);
+    }
+
+    #[test]
+    fn parent_cycles_are_broken() {
+        let transforms = [
+            translated(1.0, 0.0, 0.0, Some(2)),
+            translated(0.0, 2.0, 0.0, Some(0)),
+            translated(0.0, 0.0, 3.0, Some(1)),
+            translated(0.0, 0.0, 0.0, Some(3)),
+        ];
+        let matrices = model_matrices(&transforms);
+        assert_eq!(matrices.len(), 4);
+        // the climb from 0 stops at 1, whose parent 0 is already in the chain, so
+        // 1 is treated as having no parent
+        assert_eq!(translation(&matrices[1]), cgmath::Vector3::new(0.0, 2.0, 0.0));
+        assert_eq!(translation(&matrices[2]), cgmath::Vector3::new(0.0, 2.0, 3.0));
+        assert_eq!(translation(&matrices[0]), cgmath::Vector3::new(1.0, 2.0, 3.0));
+        assert_eq!(matrices[3], cgmath::Matrix4::identity());
+    }
+
+    #[test]
+    fn normal_matrix_undoes_non_uniform_scale() {
+        let transform = Transform { scale: cgmath::Vector3::new(2.0, 1.0, 1.0), ..Transform::default() };
+        let normal = normal_matrix(&transform.local_matrix());
+        assert_eq!(normal * cgmath::Vector3::new(1.0, 0.0, 0.0), cgmath::Vector3::new(0.5, 0.0, 0.0));
+    }
+}
@@ -0,0 +1,117 @@
+use crate::gl;
+use crate::gl_object;
+use crate::glsl;
+use crate::glyph_atlas::GlyphAtlas;
+use crate::text_renderer::TextRenderer;
+use rusttype::Font;
+
+// glyphs are rasterized at this pixel height, then scaled on the gpu
+const PIXEL_HEIGHT: f32 = 48.0;
+
+const VERTEX_SHADER_CODE: &str = r"
+layout (location=0) in vec2 position;
+layout (location=1) in vec2 tex_coords;
+uniform mat4 transform;
+out vec2 v_tex_coords;
+void main() {
+    gl_Position = transform * vec4(position, 0.0, 1.0);
+    v_tex_coords = tex_coords;
+}
+";
+
+const FRAGMENT_SHADER_CODE: &str = r"
+in vec2 v_tex_coords;
+out vec4 f_color;
+uniform sampler2D atlas;
+void main() {
+    f_color = vec4(1.0, 1.0, 1.0, texture(atlas, v_tex_coords).r);
+}
+";
+
+pub struct TrueTypeTextContext {
+    atlas: GlyphAtlas,
+    program: gl_object::Program,
+    buffer: gl_object::Buffer,
+    vao: gl_object::VertexArray,
+    texture: gl_object::Texture,
+    transform_uniform_location: i32,
+}
+
+impl TrueTypeTextContext {
+    pub fn from_file<P: AsRef<std::path::Path>>(path: P) -> Result<TrueTypeTextContext, Box<dyn std::error::Error>> {
+        let bytes = std::fs::read(path)?;
+        let font = Font::from_bytes(bytes)?;
+        TrueTypeTextContext::new(font)
+    }
+
+    pub fn new(font: Font<'static>) -> Result<TrueTypeTextContext, Box<dyn std::error::Error>> {
+        let atlas = GlyphAtlas::new(font, PIXEL_HEIGHT, 0, 1, |raster, _, _| raster.to_vec());
+
+        let (program, _) = glsl::build_program("truetype text", VERTEX_SHADER_CODE, FRAGMENT_SHADER_CODE)?;
+
+        let transform_uniform_location = glsl::uniform_location(&program, "transform")?;
+        let atlas_uniform_location = glsl::uniform_location(&program, "atlas")?;
+
+        let texture = atlas.create_texture();
+        unsafe {
+            gl::UseProgram(program.id());
+            gl::Uniform1i(atlas_uniform_location, 0);
+        }
+
+        let buffer = gl_object::Buffer::new();
+        let vao = gl_helpers::create_single_buffer_vertex_array(buffer.id(), &[2, 2])?;
+        let vao = gl_object::VertexArray::from_raw(vao);
+
+        Ok(TrueTypeTextContext {
+            atlas,
+            program,
+            buffer,
+            vao,
+            texture,
+            transform_uniform_location,
+        })
+    }
+}
+
+impl TextRenderer for TrueTypeTextContext {
+    fn name(&self) -> &str {
+        "truetype"
+    }
+
+    fn measure(&self, string: &str) -> f32 {
+        self.atlas.measure(string)
+    }
+
+    fn draw_text_transformed(&self, string: &str, transform: &cgmath::Matrix4<f32>) {
+        let vertices = self.atlas.build_vertices(string);
+        if vertices.is_empty() {
+            return;
+        }
+
+        unsafe {
+            use cgmath::Matrix;
+            gl::UseProgram(self.program.id());
+            gl::UniformMatrix4fv(self.transform_uniform_location, 1, gl::FALSE, transform.as_ptr());
+
+            gl::BindBuffer(gl::ARRAY_BUFFER, self.buffer.id());
+            gl::BufferData(gl::ARRAY_BUFFER,
+                (vertices.len() * std::mem::size_of::<f32>()) as isize,
+                vertices.as_ptr() as *const std::os::raw::c_void,
+                gl::DYNAMIC_DRAW);
+
+            gl::ActiveTexture(gl::TEXTURE0);
+            gl::BindTexture(gl::TEXTURE_2D, self.texture.id());
+
+            // glyph quads overlap slightly after kerning, so don't let them depth test each other away
+            gl::Enable(gl::BLEND);
+            gl::BlendFunc(gl::SRC_ALPHA, gl::ONE_MINUS_SRC_ALPHA);
+            gl::DepthMask(gl::FALSE);
+
+            gl::BindVertexArray(self.vao.id());
+            gl::DrawArrays(gl::TRIANGLES, 0, (vertices.len() / 4) as i32);
+
+            gl::DepthMask(gl::TRUE);
+            gl::Disable(gl::BLEND);
+        }
+    }
+}
@@ -0,0 +1,267 @@
+// a small immediate-mode gui. widgets are declared every frame between `begin` and
+// `end`, return whether they were used, and get drawn all at once by `end`.
+use crate::shape_renderer::{Color, ShapeRenderer};
+use crate::text_renderer::TextRenderer;
+
+const TEXT_PIXELS: f32 = 10.0;
+const ROW_HEIGHT: f32 = 20.0;
+const PADDING: f32 = 6.0;
+
+const PANEL_COLOR: Color = [0.1, 0.1, 0.12, 0.85];
+const FILL_COLOR: Color = [0.25, 0.3, 0.45, 1.0];
+const WIDGET_COLOR: Color = [0.3, 0.3, 0.35, 1.0];
+const HOT_COLOR: Color = [0.4, 0.4, 0.5, 1.0];
+const ACTIVE_COLOR: Color = [0.5, 0.55, 0.75, 1.0];
+const GRAPH_COLOR: Color = [0.4, 0.9, 0.4, 1.0];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct WidgetId(u64);
+
+fn widget_id(label: &str, index: usize) -> WidgetId {
+    use std::hash::{Hash, Hasher};
+    let mut hasher = std::collections::hash_map::DefaultHasher::new();
+    label.hash(&mut hasher);
+    index.hash(&mut hasher);
+    WidgetId(hasher.finish())
+}
+
+pub struct Ui {
+    mouse_position: [f32;2],
+    mouse_down: bool,
+    mouse_pressed: bool,
+    mouse_released: bool,
+    // widget the mouse went down on, which keeps the mouse until it's released
+    active: Option<WidgetId>,
+    widget_count: usize,
+
+    // layout cursor, within the current panel
+    x: f32,
+    y: f32,
+    width: f32,
+    panel_top: f32,
+    // the panels declared so far this frame, and those from the last frame, which
+    // are where clicks go to the ui rather than the scene
+    panels: Vec<[f32;4]>,
+    last_panels: Vec<[f32;4]>,
+
+    rects: Vec<([f32;4], Color)>,
+    lines: Vec<(Vec<[f32;2]>, Color)>,
+    texts: Vec<(String, f32, f32)>,
+}
+
+impl Ui {
+    pub fn new() -> Ui {
+        Ui {
+            mouse_position: [0.0, 0.0],
+            mouse_down: false,
+            mouse_pressed: false,
+            mouse_released: false,
+            active: None,
+            widget_count: 0,
+            x: 0.0,
+            y: 0.0,
+            width: 0.0,
+            panel_top: 0.0,
+            panels: Vec::new(),
+            last_panels: Vec::new(),
+            rects: Vec::new(),
+            lines: Vec::new(),
+            texts: Vec::new(),
+        }
+    }
+
+    // returns true if the ui used the event, so it shouldn't go to the scene as well
+    pub fn handle_event(&mut self, event: &glutin::WindowEvent) -> bool {
+        use glutin::WindowEvent::*;
+        match event {
+            CursorMoved{position, ..} => {
+                self.mouse_position = [position.x as f32, position.y as f32];
+                self.active.is_some()
+            },
+            MouseInput{state, button: glutin::MouseButton::Left, ..} => {
+                let down = *state == glutin::ElementState::Pressed;
+                if down && !self.mouse_down {
+                    // clicks outside the panels are the scene's
+                    let over_panel = self.last_panels.iter().any(|&rect| self.hovered(rect));
+                    if !over_panel && self.active.is_none() {
+                        return false;
+                    }
+                    self.mouse_pressed = true;
+                } else if !down && self.mouse_down {
+                    self.mouse_released = true;
+                } else {
+                    // a repeated press the ui already has, or the release of a
+                    // press it didn't take
+                    return down;
+                }
+                self.mouse_down = down;
+                true
+            },
+            _ => false,
+        }
+    }
+
+    // start a panel with its top left corner at (x, y), in window pixels
+    pub fn begin(&mut self, x: f32, y: f32, width: f32) {
+        self.close_panel();
+        self.x = x;
+        self.y = y;
+        self.width = width;
+        self.panel_top = y;
+        self.widget_count = 0;
+    }
+
+    fn close_panel(&mut self) {
+        if self.y > self.panel_top {
+            self.panels.push([self.x, self.panel_top, self.width, self.y - self.panel_top]);
+        }
+        self.panel_top = self.y;
+    }
+
+    fn next_row(&mut self) -> [f32;4] {
+        let row = [self.x + PADDING, self.y + PADDING * 0.5, self.width - PADDING * 2.0, ROW_HEIGHT - PADDING];
+        self.rects.push(([self.x, self.y, self.width, ROW_HEIGHT], PANEL_COLOR));
+        self.y += ROW_HEIGHT;
+        row
+    }
+
+    fn hovered(&self, rect: [f32;4]) -> bool {
+        let [mx, my] = self.mouse_position;
+        mx >= rect[0] && mx < rect[0] + rect[2] && my >= rect[1] && my < rect[1] + rect[3]
+    }
+
+    // handles the press/hold/release cycle shared by the clickable widgets.
+    // returns whether the widget is active (held), and whether it was clicked this frame.
+    fn interact(&mut self, id: WidgetId, rect: [f32;4]) -> (bool, bool) {
+        let hovered = self.hovered(rect);
+        if self.mouse_pressed && hovered && self.active.is_none() {
+            self.active = Some(id);
+        }
+
+        let active = self.active == Some(id);
+        let clicked = active && self.mouse_released && hovered;
+        let color = if active { ACTIVE_COLOR } else if hovered { HOT_COLOR } else { WIDGET_COLOR };
+        self.rects.push((rect, color));
+        (active, clicked)
+    }
+
+    fn text(&mut self, text: &str, x: f32, rect: [f32;4]) {
+        // baseline a little above the bottom of the row
+        let baseline = rect[1] + rect[3] - (rect[3] - TEXT_PIXELS) * 0.5;
+        self.texts.push((text.to_string(), x, baseline));
+    }
+
+    pub fn label(&mut self, text: &str) {
+        let row = self.next_row();
+        self.text(text, row[0], row);
+    }
+
+    // collapsible section header. widgets that belong under it should only be
+    // declared when `open` is true.
+    pub fn header(&mut self, text: &str, open: &mut bool) -> bool {
+        let id = widget_id(text, self.widget_count);
+        self.widget_count += 1;
+        let row = self.next_row();
+        let (_, clicked) = self.interact(id, row);
+        if clicked {
+            *open = !*open;
+        }
+        let marker = if *open { "-" } else { "+" };
+        self.text(&format!("{} {}", marker, text), row[0] + PADDING, row);
+        clicked
+    }
+
+    pub fn button(&mut self, text: &str) -> bool {
+        let id = widget_id(text, self.widget_count);
+        self.widget_count += 1;
+        let row = self.next_row();
+        let (_, clicked) = self.interact(id, row);
+        self.text(text, row[0] + PADDING, row);
+        clicked
+    }
+
+    pub fn checkbox(&mut self, text: &str, value: &mut bool) -> bool {
+        let id = widget_id(text, self.widget_count);
+        self.widget_count += 1;
+        let row = self.next_row();
+        let bx = [row[0], row[1], row[3], row[3]];
+        let (_, clicked) = self.interact(id, bx);
+        if *value {
+            let inset = row[3] * 0.25;
+            self.rects.push(([bx[0] + inset, bx[1] + inset, bx[2] - inset * 2.0, bx[3] - inset * 2.0], GRAPH_COLOR));
+        }
+        if clicked {
+            *value = !*value;
+        }
+        self.text(text, row[0] + row[3] + PADDING, row);
+        clicked
+    }
+
+    pub fn slider(&mut self, text: &str, value: &mut f32, min: f32, max: f32) -> bool {
+        let id = widget_id(text, self.widget_count);
+        self.widget_count += 1;
+        let row = self.next_row();
+        let (active, _) = self.interact(id, row);
+
+        let mut changed = false;
+        if active && max > min {
+            let t = ((self.mouse_position[0] - row[0]) / row[2]).clamp(0.0, 1.0);
+            let new_value = min + t * (max - min);
+            changed = new_value != *value;
+            *value = new_value;
+        }
+
+        let t = if max > min { ((*value - min) / (max - min)).clamp(0.0, 1.0) } else { 0.0 };
+        self.rects.push(([row[0], row[1], row[2] * t, row[3]], FILL_COLOR));
+        self.text(&format!("{}: {:.2}", text, value), row[0] + PADDING, row);
+        changed
+    }
+
+    // line graph of `values`, scaled so that `max` reaches the top
+    pub fn graph(&mut self, values: &[f32], max: f32, height: f32) {
+        let top = self.y;
+        self.rects.push(([self.x, self.y, self.width, height], PANEL_COLOR));
+        self.y += height;
+
+        let (x0, w) = (self.x + PADDING, self.width - PADDING * 2.0);
+        let (y0, h) = (top + height - PADDING * 0.5, height - PADDING);
+        if values.len() < 2 || max <= 0.0 {
+            return;
+        }
+        let step = w / (values.len() - 1) as f32;
+        let points = values.iter().enumerate()
+            .map(|(i, v)| [x0 + i as f32 * step, y0 - (v / max).clamp(0.0, 1.0) * h])
+            .collect();
+        self.lines.push((points, GRAPH_COLOR));
+    }
+
+    // draw everything declared this frame, and get ready for the next
+    pub fn end(&mut self, shapes: &mut ShapeRenderer, text: &dyn TextRenderer, window_size: (u32, u32)) {
+        self.close_panel();
+        self.last_panels = std::mem::take(&mut self.panels);
+        if self.mouse_released {
+            self.active = None;
+        }
+        self.mouse_pressed = false;
+        self.mouse_released = false;
+
+        for (rect, color) in self.rects.drain(..) {
+            shapes.rect(rect[0], rect[1], rect[2], rect[3], color);
+        }
+        for (points, color) in self.lines.drain(..) {
+            shapes.line_strip(&points, color);
+        }
+        shapes.flush(window_size);
+
+        if window_size.0 == 0 || window_size.1 == 0 {
+            self.texts.clear();
+            return;
+        }
+        let (w, h) = (window_size.0 as f32, window_size.1 as f32);
+        let xscale = 2.0 * TEXT_PIXELS / w;
+        let yscale = 2.0 * TEXT_PIXELS / h;
+        for (string, x, y) in self.texts.drain(..) {
+            text.draw_text(&string, -1.0 + 2.0 * x / w, 1.0 - 2.0 * y / h, xscale, yscale);
+        }
+    }
+}